@@ -8,6 +8,7 @@ use std::collections::HashMap;
 #[doc = " capabilities defined in each worker's JSON configuration file."]
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(package = "improbable")]
 pub struct WorkerAttributeSet {
     #[doc = " A particular capability is just an arbitrary string. A particular worker's attribute set must"]
     #[doc = " contain _all_ of these attributes in order to satisfy this WorkerAttributeSet."]
@@ -28,6 +29,7 @@ pub struct WorkerAttributeSet {
 #[doc = "   }"]
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(package = "improbable")]
 pub struct WorkerRequirementSet {
     #[doc = " A worker satisfies this WorkerRequirementSet if it satisfies _any_ of these"]
     #[doc = " WorkerAttributeSets (i.e. if any one of these WorkerAttributeSets is a subset of the worker's"]
@@ -40,6 +42,7 @@ pub struct WorkerRequirementSet {
 #[doc = " standard Position component, below, but can also be reused for other purposes."]
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(package = "improbable", query)]
 pub struct Coordinates {
     #[field_id(1u32)]
     #[spatial_type("double")]
@@ -54,6 +57,7 @@ pub struct Coordinates {
 #[doc = " A type representing the dimensions of a cuboid."]
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(package = "improbable", query)]
 pub struct EdgeLength {
     #[field_id(1u32)]
     #[spatial_type("double")]
@@ -67,6 +71,7 @@ pub struct EdgeLength {
 }
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(package = "improbable", name = "ComponentInterest.Query", query)]
 pub struct Query {
     #[field_id(1u32)]
     #[spatial_type("type")]
@@ -96,6 +101,11 @@ pub struct Query {
 }
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(
+    package = "improbable",
+    name = "ComponentInterest.QueryConstraint",
+    query
+)]
 pub struct QueryConstraint {
     #[doc = " Only one constraint should be provided. Providing more than one is invalid."]
     #[field_id(1u32)]
@@ -135,6 +145,11 @@ pub struct QueryConstraint {
 }
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(
+    package = "improbable",
+    name = "ComponentInterest.SphereConstraint",
+    query
+)]
 pub struct SphereConstraint {
     #[field_id(1u32)]
     #[spatial_type("type")]
@@ -145,6 +160,11 @@ pub struct SphereConstraint {
 }
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(
+    package = "improbable",
+    name = "ComponentInterest.CylinderConstraint",
+    query
+)]
 pub struct CylinderConstraint {
     #[field_id(1u32)]
     #[spatial_type("type")]
@@ -155,6 +175,11 @@ pub struct CylinderConstraint {
 }
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(
+    package = "improbable",
+    name = "ComponentInterest.BoxConstraint",
+    query
+)]
 pub struct BoxConstraint {
     #[field_id(1u32)]
     #[spatial_type("type")]
@@ -165,6 +190,11 @@ pub struct BoxConstraint {
 }
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(
+    package = "improbable",
+    name = "ComponentInterest.RelativeSphereConstraint",
+    query
+)]
 pub struct RelativeSphereConstraint {
     #[field_id(1u32)]
     #[spatial_type("double")]
@@ -172,6 +202,11 @@ pub struct RelativeSphereConstraint {
 }
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(
+    package = "improbable",
+    name = "ComponentInterest.RelativeCylinderConstraint",
+    query
+)]
 pub struct RelativeCylinderConstraint {
     #[field_id(1u32)]
     #[spatial_type("double")]
@@ -179,6 +214,11 @@ pub struct RelativeCylinderConstraint {
 }
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(
+    package = "improbable",
+    name = "ComponentInterest.RelativeBoxConstraint",
+    query
+)]
 pub struct RelativeBoxConstraint {
     #[field_id(1u32)]
     #[spatial_type("type")]
@@ -187,9 +227,15 @@ pub struct RelativeBoxConstraint {
 #[doc = " The self constraint matches the entity the Interest query is attached to."]
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(
+    package = "improbable",
+    name = "ComponentInterest.SelfConstraint",
+    query
+)]
 pub struct SelfConstraint {}
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(package = "improbable")]
 pub struct ComponentInterest {
     #[field_id(1u32)]
     #[spatial_type("list<type>")]
@@ -197,11 +243,13 @@ pub struct ComponentInterest {
 }
 #[allow(dead_code)]
 #[derive(SpatialType)]
+#[spatial(package = "improbable")]
 pub struct ShardedMap {}
 #[doc = " The EntityAcl component defines what sorts of workers can read and write each entity in the"]
 #[doc = " simulation. This component is REQUIRED (every entity must be created with it)."]
 #[allow(dead_code)]
 #[derive(SpatialComponent)]
+#[spatial(package = "improbable")]
 #[id(50u32)]
 pub struct EntityAcl {
     #[doc = " The read ACL defined the kinds of workers that may check out the entity. Note that a worker"]
@@ -223,6 +271,7 @@ pub struct EntityAcl {
 #[doc = " the entity. This component is optional."]
 #[allow(dead_code)]
 #[derive(SpatialComponent)]
+#[spatial(package = "improbable")]
 #[id(53u32)]
 pub struct Metadata {
     #[doc = " The entity type is a string describing what kind of thing the entity represents"]
@@ -239,6 +288,7 @@ pub struct Metadata {
 #[doc = " of position, and simply update this component as necessary (perhaps less frequently) for"]
 #[doc = " authority delegation."]
 #[derive(SpatialComponent)]
+#[spatial(package = "improbable")]
 #[id(54u32)]
 #[allow(dead_code)]
 pub struct Position {
@@ -250,6 +300,7 @@ pub struct Position {
 #[doc = " persisted in simulation snapshots. Any entity without this component will be dropped when a"]
 #[doc = " snapshot is taken."]
 #[derive(SpatialComponent)]
+#[spatial(package = "improbable")]
 #[id(55u32)]
 #[allow(dead_code)]
 pub struct Persistence {}
@@ -259,6 +310,7 @@ pub struct Persistence {}
 #[doc = " which match the corresponding queries."]
 #[allow(dead_code)]
 #[derive(SpatialComponent)]
+#[spatial(package = "improbable")]
 #[id(58u32)]
 pub struct Interest {
     #[field_id(1u32)]
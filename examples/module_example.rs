@@ -7,6 +7,7 @@ mod generated {
             #[allow(unused_imports)]
             use std::collections::HashMap;
             #[derive(SpatialEnum, Clone, Debug)]
+            #[spatial(package = "improbable.restricted")]
             pub enum ConnectionStatus {
                 #[value(0u32)]
                 Unknown,
@@ -23,6 +24,7 @@ mod generated {
             #[allow(dead_code)]
             #[doc = " Represents data relevant to the connection between the Runtime and the worker."]
             #[derive(SpatialType)]
+            #[spatial(package = "improbable.restricted")]
             pub struct Connection {
                 #[field_id(1u32)]
                 #[spatial_type("enum")]
@@ -44,13 +46,16 @@ mod generated {
             #[allow(dead_code)]
             #[doc = " A request-response pair to disconnect a worker from a running deployment."]
             #[derive(SpatialType)]
+            #[spatial(package = "improbable.restricted")]
             pub struct DisconnectRequest {}
             #[allow(dead_code)]
             #[derive(SpatialType)]
+            #[spatial(package = "improbable.restricted")]
             pub struct DisconnectResponse {}
             #[allow(dead_code)]
             #[doc = " A bundle of data that can be used to uniquely identify a player."]
             #[derive(SpatialType)]
+            #[spatial(package = "improbable.restricted")]
             pub struct PlayerIdentity {
                 #[doc = " A player identifier is unique within the context of a single provider."]
                 #[field_id(1u32)]
@@ -79,12 +84,14 @@ mod generated {
             #[doc = " SpatialOS runtime system entity."]
             #[doc = " It is present on all entities with any of the components below."]
             #[derive(SpatialComponent)]
+            #[spatial(package = "improbable.restricted")]
             #[id(59u32)]
             pub struct System {}
             #[allow(dead_code)]
             #[doc = " The Worker component indicates that the system entity it is on represents a worker."]
             #[doc = " It carries metadata identifying that worker."]
             #[derive(SpatialComponent)]
+            #[spatial(package = "improbable.restricted")]
             #[id(60u32)]
             pub struct Worker {
                 #[field_id(1u32)]
@@ -102,6 +109,7 @@ mod generated {
             #[doc = " These are identified by the Runtime as workers that have connected with a player identity token."]
             #[doc = " The contents of this token are exposed in this component."]
             #[derive(SpatialComponent)]
+            #[spatial(package = "improbable.restricted")]
             #[id(61u32)]
             pub struct PlayerClient {
                 #[field_id(1u32)]
@@ -0,0 +1,8 @@
+//! Functions referenced by the generated code to check the types of `type` and `enum` fields
+//! where they are declared.
+
+use crate::schema::{SchemaEnum, SchemaType};
+
+pub fn derives_spatial_type<T: SchemaType>() {}
+
+pub fn derives_spatial_enum<T: SchemaEnum>() {}
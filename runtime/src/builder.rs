@@ -0,0 +1,11 @@
+//! Markers of the `FooData::builder()` and `FooUpdate::builder()` generated for every type,
+//! which start from the default values. The `Data` builder only has a `build` method once
+//! every single-valued field without a default has been set.
+
+/// Marks a required field of a generated `Data` builder which has been set.
+#[derive(Debug, Clone, Copy)]
+pub struct Set;
+
+/// Marks a required field of a generated `Data` builder which has not been set yet.
+#[derive(Debug, Clone, Copy)]
+pub struct Unset;
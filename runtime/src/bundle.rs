@@ -0,0 +1,175 @@
+//! Descriptions of the schema items, modelled after the bundle JSON of the schema compiler.
+
+#[cfg(feature = "json")]
+use serde_json::{json, Value};
+
+use crate::schema::FieldId;
+
+/// Returned by the `schema_bundle_entry` function of every derived item.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleEntry {
+    Type(TypeDefinition),
+    Component(ComponentDefinition),
+    Enum(EnumDefinition),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeDefinition {
+    pub qualified_name: &'static str,
+    pub fields: Vec<FieldDefinition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentDefinition {
+    pub qualified_name: &'static str,
+    pub component_id: u32,
+    pub fields: Vec<FieldDefinition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDefinition {
+    pub qualified_name: &'static str,
+    pub values: Vec<EnumValueDefinition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumValueDefinition {
+    pub name: &'static str,
+    pub value: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDefinition {
+    pub name: &'static str,
+    pub field_id: FieldId,
+    pub ty: FieldType,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldType {
+    Singular(ValueType),
+    Option(ValueType),
+    List(ValueType),
+    Map(ValueType, ValueType),
+}
+
+/// A primitive by its schema name, e.g. `EntityId`, or a type or enum by its qualified name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueType {
+    Primitive(&'static str),
+    Type(&'static str),
+    Enum(&'static str),
+}
+
+impl BundleEntry {
+    pub fn qualified_name(&self) -> &'static str {
+        match self {
+            Self::Type(definition) => definition.qualified_name,
+            Self::Component(definition) => definition.qualified_name,
+            Self::Enum(definition) => definition.qualified_name,
+        }
+    }
+}
+
+/// Builds the bundle of the entries, sorted by qualified name:
+/// `{"v1": {"typeDefinitions": [..], "componentDefinitions": [..], "enumDefinitions": [..]}}`.
+#[cfg(feature = "json")]
+pub fn to_json(entries: &[BundleEntry]) -> Value {
+    let mut entries = entries.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.qualified_name());
+    let mut types = Vec::new();
+    let mut components = Vec::new();
+    let mut enums = Vec::new();
+    for entry in entries {
+        match entry {
+            BundleEntry::Type(definition) => types.push(json!({
+                "qualifiedName": definition.qualified_name,
+                "fieldDefinitions": fields_to_json(&definition.fields),
+            })),
+            BundleEntry::Component(definition) => components.push(json!({
+                "qualifiedName": definition.qualified_name,
+                "componentId": definition.component_id,
+                "fieldDefinitions": fields_to_json(&definition.fields),
+            })),
+            BundleEntry::Enum(definition) => enums.push(json!({
+                "qualifiedName": definition.qualified_name,
+                "valueDefinitions": definition
+                    .values
+                    .iter()
+                    .map(|value| json!({ "name": value.name, "value": value.value }))
+                    .collect::<Vec<_>>(),
+            })),
+        }
+    }
+    json!({
+        "v1": {
+            "typeDefinitions": types,
+            "componentDefinitions": components,
+            "enumDefinitions": enums,
+        }
+    })
+}
+
+#[cfg(feature = "json")]
+fn fields_to_json(fields: &[FieldDefinition]) -> Vec<Value> {
+    fields
+        .iter()
+        .map(|field| {
+            let mut value = json!({ "name": field.name, "fieldId": field.field_id });
+            let (key, ty) = match &field.ty {
+                FieldType::Singular(ty) => {
+                    ("singularType", json!({ "type": value_type_to_json(ty) }))
+                }
+                FieldType::Option(ty) => {
+                    ("optionType", json!({ "innerType": value_type_to_json(ty) }))
+                }
+                FieldType::List(ty) => ("listType", json!({ "innerType": value_type_to_json(ty) })),
+                FieldType::Map(key, ty) => (
+                    "mapType",
+                    json!({
+                        "keyType": value_type_to_json(key),
+                        "valueType": value_type_to_json(ty),
+                    }),
+                ),
+            };
+            value[key] = ty;
+            value
+        })
+        .collect()
+}
+
+#[cfg(feature = "json")]
+fn value_type_to_json(ty: &ValueType) -> Value {
+    match ty {
+        ValueType::Primitive(name) => json!({ "primitive": name }),
+        ValueType::Type(name) => json!({ "type": name }),
+        ValueType::Enum(name) => json!({ "enum": name }),
+    }
+}
+
+/// Submitted by every derived item when the `registry` feature is enabled.
+#[cfg(feature = "registry")]
+#[derive(Debug)]
+pub struct BundleRegistration {
+    pub entry: fn() -> BundleEntry,
+}
+
+#[cfg(feature = "registry")]
+inventory::collect!(BundleRegistration);
+
+/// The entries of every item linked into the binary, sorted by qualified name.
+#[cfg(feature = "registry")]
+pub fn entries() -> Vec<BundleEntry> {
+    let mut entries = inventory::iter::<BundleRegistration>
+        .into_iter()
+        .map(|registration| (registration.entry)())
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.qualified_name());
+    entries
+}
+
+/// The bundle of every item linked into the binary.
+#[cfg(all(feature = "registry", feature = "json"))]
+pub fn collect_json() -> Value {
+    to_json(&entries())
+}
@@ -0,0 +1,283 @@
+//! Snapshot JSON of the generated `Data` structs, with the `json` feature.
+//!
+//! `FooData::to_snapshot_json` keys every field by its id next to its schema type, e.g.
+//! `{"1": {"type": "double", "value": 1.0}}`, and `from_snapshot_json` reads it back.
+//! Non-finite floats are written as the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+
+use ::base64::engine::general_purpose::STANDARD;
+use ::base64::Engine;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::Hash;
+
+pub use serde_json::{Map, Value};
+
+use crate::schema::FieldId;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonError {
+    NotAnObject,
+    MissingField(FieldId),
+    TypeMismatch {
+        field_id: FieldId,
+        expected: String,
+        found: String,
+    },
+    InvalidValue(String),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAnObject => write!(f, "expected an object of fields"),
+            Self::MissingField(field_id) => write!(f, "missing field {}", field_id),
+            Self::TypeMismatch {
+                field_id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "field {} should be of type {} but is of type {}",
+                field_id, expected, found
+            ),
+            Self::InvalidValue(message) => write!(f, "invalid value: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+/// A value of a field in the snapshot JSON format.
+///
+/// The generated `Data` structs are objects keyed by field id, each field
+/// holding its schema type and its value: `{"1": {"type": "double", "value": 1.0}}`.
+/// `bytes` are base64 strings, enums their numeric value, options `null` or
+/// their value and maps lists of `{"key": .., "value": ..}` objects. `NaN` and
+/// infinite floats are the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+pub trait SnapshotJson: Sized {
+    fn to_json(&self) -> Value;
+
+    fn from_json(value: &Value) -> Result<Self, JsonError>;
+}
+
+pub fn field(schema_type: &str, value: Value) -> Value {
+    let mut field = Map::new();
+    field.insert("type".to_owned(), Value::String(schema_type.to_owned()));
+    field.insert("value".to_owned(), value);
+    Value::Object(field)
+}
+
+pub fn fields(value: &Value) -> Result<&Map<String, Value>, JsonError> {
+    value.as_object().ok_or(JsonError::NotAnObject)
+}
+
+pub fn get_field<T: SnapshotJson>(
+    fields: &Map<String, Value>,
+    field_id: FieldId,
+    schema_type: &str,
+) -> Result<T, JsonError> {
+    let field = fields
+        .get(&field_id.to_string())
+        .ok_or(JsonError::MissingField(field_id))?;
+    let found = field
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if found != schema_type {
+        return Err(JsonError::TypeMismatch {
+            field_id,
+            expected: schema_type.to_owned(),
+            found: found.to_owned(),
+        });
+    }
+    T::from_json(field.get("value").unwrap_or(&Value::Null))
+}
+
+/// Like `get_field`, but a missing field is `None`, for fields which older versions may lack.
+pub fn get_optional_field<T: SnapshotJson>(
+    fields: &Map<String, Value>,
+    field_id: FieldId,
+    schema_type: &str,
+) -> Result<Option<T>, JsonError> {
+    if fields.contains_key(&field_id.to_string()) {
+        get_field(fields, field_id, schema_type).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+fn invalid<T>(expected: &str, value: &Value) -> Result<T, JsonError> {
+    Err(JsonError::InvalidValue(format!(
+        "expected {}, found {}",
+        expected, value
+    )))
+}
+
+macro_rules! implement_integers {
+    ($($ty:ty, $as:ident;)*) => {
+        $(
+            impl SnapshotJson for $ty {
+                fn to_json(&self) -> Value {
+                    Value::from(*self)
+                }
+
+                fn from_json(value: &Value) -> Result<Self, JsonError> {
+                    match value.$as().map(<$ty>::try_from) {
+                        Some(Ok(value)) => Ok(value),
+                        _ => invalid(stringify!($ty), value),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+implement_integers! {
+    i32, as_i64;
+    i64, as_i64;
+    u32, as_u64;
+    u64, as_u64;
+}
+
+impl SnapshotJson for bool {
+    fn to_json(&self) -> Value {
+        Value::Bool(*self)
+    }
+
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        value.as_bool().map_or_else(|| invalid("bool", value), Ok)
+    }
+}
+
+/// JSON has no number for non-finite floats, they are written as the strings `"NaN"`,
+/// `"Infinity"` and `"-Infinity"`.
+macro_rules! implement_floats {
+    ($($ty:ident;)*) => {
+        $(
+            impl SnapshotJson for $ty {
+                fn to_json(&self) -> Value {
+                    if self.is_nan() {
+                        Value::from("NaN")
+                    } else if self.is_infinite() && self.is_sign_positive() {
+                        Value::from("Infinity")
+                    } else if self.is_infinite() {
+                        Value::from("-Infinity")
+                    } else {
+                        Value::from(*self)
+                    }
+                }
+
+                fn from_json(value: &Value) -> Result<Self, JsonError> {
+                    match value {
+                        Value::String(name) => match name.as_str() {
+                            "NaN" => Ok($ty::NAN),
+                            "Infinity" => Ok($ty::INFINITY),
+                            "-Infinity" => Ok($ty::NEG_INFINITY),
+                            _ => invalid(stringify!($ty), value),
+                        },
+                        value => value.as_f64().map_or_else(
+                            || invalid(stringify!($ty), value),
+                            |value| Ok(value as $ty),
+                        ),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+implement_floats! {
+    f32;
+    f64;
+}
+
+impl SnapshotJson for String {
+    fn to_json(&self) -> Value {
+        Value::String(self.clone())
+    }
+
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        value
+            .as_str()
+            .map_or_else(|| invalid("string", value), |value| Ok(value.to_owned()))
+    }
+}
+
+impl SnapshotJson for Vec<u8> {
+    fn to_json(&self) -> Value {
+        Value::String(STANDARD.encode(self))
+    }
+
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        match value.as_str().map(|value| STANDARD.decode(value)) {
+            Some(Ok(bytes)) => Ok(bytes),
+            _ => invalid("base64 string", value),
+        }
+    }
+}
+
+impl<T: SnapshotJson> SnapshotJson for Vec<T> {
+    fn to_json(&self) -> Value {
+        Value::Array(self.iter().map(SnapshotJson::to_json).collect())
+    }
+
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        match value.as_array() {
+            Some(values) => values.iter().map(T::from_json).collect(),
+            None => invalid("list", value),
+        }
+    }
+}
+
+impl<T: SnapshotJson> SnapshotJson for Option<T> {
+    fn to_json(&self) -> Value {
+        self.as_ref().map_or(Value::Null, SnapshotJson::to_json)
+    }
+
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        match value {
+            Value::Null => Ok(None),
+            value => T::from_json(value).map(Some),
+        }
+    }
+}
+
+impl<T: SnapshotJson> SnapshotJson for Box<T> {
+    fn to_json(&self) -> Value {
+        T::to_json(self)
+    }
+
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        T::from_json(value).map(Box::new)
+    }
+}
+
+impl<K: SnapshotJson + Eq + Hash, V: SnapshotJson> SnapshotJson for HashMap<K, V> {
+    fn to_json(&self) -> Value {
+        Value::Array(
+            self.iter()
+                .map(|(key, value)| {
+                    let mut entry = Map::new();
+                    entry.insert("key".to_owned(), key.to_json());
+                    entry.insert("value".to_owned(), value.to_json());
+                    Value::Object(entry)
+                })
+                .collect(),
+        )
+    }
+
+    fn from_json(value: &Value) -> Result<Self, JsonError> {
+        match value.as_array() {
+            Some(entries) => entries
+                .iter()
+                .map(|entry| {
+                    let key = K::from_json(entry.get("key").unwrap_or(&Value::Null))?;
+                    let value = V::from_json(entry.get("value").unwrap_or(&Value::Null))?;
+                    Ok((key, value))
+                })
+                .collect(),
+            None => invalid("map", value),
+        }
+    }
+}
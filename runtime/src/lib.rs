@@ -0,0 +1,28 @@
+pub mod assertions;
+pub mod builder;
+pub mod bundle;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod query;
+#[cfg(feature = "registry")]
+pub mod registry;
+pub mod schema;
+#[cfg(feature = "serde")]
+pub mod serialization;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+pub mod template;
+#[cfg(feature = "roundtrip")]
+pub mod testing;
+
+#[cfg(feature = "registry")]
+pub use inventory;
+#[cfg(feature = "roundtrip")]
+pub use proptest;
+#[cfg(feature = "serde")]
+pub use serde;
+
+pub use schema::{
+    FieldCountError, SchemaComponent, SchemaComponentData, SchemaEnum, SchemaError, SchemaObject,
+    SchemaType, SchemaView,
+};
@@ -0,0 +1,290 @@
+//! Interest and entity queries built from the ids of the derived components, e.g.
+//! `Query::sphere([0.0; 3], 50.0).and(component::<Position>())`.
+//!
+//! Queries convert to the `Data` of the types generated from `improbable.ComponentInterest.Query`
+//! with [`Query::to_data`], through the [`QuerySchema`] implementations of the derived types
+//! marked with `#[spatial(query)]`.
+//! With the `sdk` feature, `Query::to_entity_query` makes the `EntityQuery` of a world command
+//! from queries limited to spheres, entity ids and components.
+
+use std::fmt;
+
+use crate::schema::{EntityId, SchemaComponent, SchemaObject, SchemaType};
+
+/// Conversions between the values of queries and the `Data` of the types generated from the
+/// standard library, implemented by `#[derive(SpatialType)]` for the types marked with
+/// `#[spatial(query)]`: `improbable.Coordinates`, `improbable.EdgeLength` and the types of
+/// `improbable.ComponentInterest`, which `include_schema!` marks itself. The field ids come
+/// from the derived types, `T` being `Query`, `Constraint` or the parameters of a constraint,
+/// e.g. `([f64; 3], f64)` for the center and radius of a sphere.
+///
+/// An empty `Or` has no encoding of its own and is written like an empty `And`, which is why
+/// [`Constraint::to_data`] and [`Query::to_data`] reject it.
+pub trait QuerySchema<T>: SchemaType {
+    fn to_query_data(value: &T) -> Self::Data;
+    fn from_query_data(data: &Self::Data) -> T;
+}
+
+/// An `Or` without constraints, which matches no entity but would be read back as an `And`
+/// matching every entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyOrError;
+
+impl fmt::Display for EmptyOrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "an Or constraint needs at least one constraint")
+    }
+}
+
+impl std::error::Error for EmptyOrError {}
+
+/// Matches the entities having the component `C`.
+pub fn component<C: SchemaComponent>() -> Constraint {
+    Constraint::Component(C::ID)
+}
+
+/// One of the constraints of `improbable.ComponentInterest.QueryConstraint`, positions and
+/// sizes being `[x, y, z]`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constraint {
+    Sphere {
+        center: [f64; 3],
+        radius: f64,
+    },
+    Cylinder {
+        center: [f64; 3],
+        radius: f64,
+    },
+    Box {
+        center: [f64; 3],
+        edge_length: [f64; 3],
+    },
+    /// Relative to the position of the entity the interest is attached to.
+    RelativeSphere {
+        radius: f64,
+    },
+    RelativeCylinder {
+        radius: f64,
+    },
+    RelativeBox {
+        edge_length: [f64; 3],
+    },
+    EntityId(EntityId),
+    Component(u32),
+    And(Vec<Constraint>),
+    Or(Vec<Constraint>),
+    /// The entity the interest is attached to.
+    SelfEntity,
+}
+
+impl Constraint {
+    /// Requires both constraints, extending `self` when it already is an `And`.
+    pub fn and(self, other: Constraint) -> Self {
+        match self {
+            Self::And(mut constraints) => {
+                constraints.push(other);
+                Self::And(constraints)
+            }
+            constraint => Self::And(vec![constraint, other]),
+        }
+    }
+
+    /// Requires either constraint, extending `self` when it already is an `Or`.
+    pub fn or(self, other: Constraint) -> Self {
+        match self {
+            Self::Or(mut constraints) => {
+                constraints.push(other);
+                Self::Or(constraints)
+            }
+            constraint => Self::Or(vec![constraint, other]),
+        }
+    }
+
+    /// Converts to the `Data` of the type generated from
+    /// `improbable.ComponentInterest.QueryConstraint`, failing if `self` contains an empty `Or`.
+    pub fn to_data<T: QuerySchema<Self>>(&self) -> Result<T::Data, EmptyOrError> {
+        self.check()?;
+        Ok(T::to_query_data(self))
+    }
+
+    /// Checks that every `Or` of the constraint has constraints.
+    pub fn check(&self) -> Result<(), EmptyOrError> {
+        match self {
+            Self::Or(constraints) if constraints.is_empty() => Err(EmptyOrError),
+            Self::And(constraints) | Self::Or(constraints) => {
+                constraints.iter().try_for_each(Self::check)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads the first constraint set in `data`, an empty one reading as an `And` of nothing.
+    pub fn from_data<T: QuerySchema<Self>>(data: &T::Data) -> Self {
+        T::from_query_data(data)
+    }
+}
+
+/// A query of `improbable.ComponentInterest`, returning every component of the matching
+/// entities unless some are picked with [`Query::with_component`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub constraint: Constraint,
+    /// Ids of the components returned, every component when `None`.
+    pub result_component_ids: Option<Vec<u32>>,
+    /// Maximum frequency of the updates in Hz, unlimited when `None`.
+    pub frequency: Option<f32>,
+}
+
+impl Query {
+    pub fn new(constraint: Constraint) -> Self {
+        Self {
+            constraint,
+            result_component_ids: None,
+            frequency: None,
+        }
+    }
+
+    pub fn sphere(center: [f64; 3], radius: f64) -> Self {
+        Self::new(Constraint::Sphere { center, radius })
+    }
+
+    pub fn cylinder(center: [f64; 3], radius: f64) -> Self {
+        Self::new(Constraint::Cylinder { center, radius })
+    }
+
+    pub fn cuboid(center: [f64; 3], edge_length: [f64; 3]) -> Self {
+        Self::new(Constraint::Box {
+            center,
+            edge_length,
+        })
+    }
+
+    pub fn relative_sphere(radius: f64) -> Self {
+        Self::new(Constraint::RelativeSphere { radius })
+    }
+
+    pub fn relative_cylinder(radius: f64) -> Self {
+        Self::new(Constraint::RelativeCylinder { radius })
+    }
+
+    pub fn relative_cuboid(edge_length: [f64; 3]) -> Self {
+        Self::new(Constraint::RelativeBox { edge_length })
+    }
+
+    pub fn entity(entity_id: EntityId) -> Self {
+        Self::new(Constraint::EntityId(entity_id))
+    }
+
+    pub fn component<C: SchemaComponent>() -> Self {
+        Self::new(component::<C>())
+    }
+
+    pub fn self_entity() -> Self {
+        Self::new(Constraint::SelfEntity)
+    }
+
+    pub fn and(mut self, constraint: Constraint) -> Self {
+        self.constraint = self.constraint.and(constraint);
+        self
+    }
+
+    pub fn or(mut self, constraint: Constraint) -> Self {
+        self.constraint = self.constraint.or(constraint);
+        self
+    }
+
+    /// Adds `C` to the components returned, which are no longer all of them.
+    pub fn with_component<C: SchemaComponent>(mut self) -> Self {
+        self.result_component_ids
+            .get_or_insert_with(Vec::new)
+            .push(C::ID);
+        self
+    }
+
+    pub fn frequency(mut self, frequency: f32) -> Self {
+        self.frequency = Some(frequency);
+        self
+    }
+
+    /// Converts to the `Data` of the type generated from `improbable.ComponentInterest.Query`,
+    /// failing if the constraint contains an empty `Or`.
+    pub fn to_data<T: QuerySchema<Self>>(&self) -> Result<T::Data, EmptyOrError> {
+        self.constraint.check()?;
+        Ok(T::to_query_data(self))
+    }
+
+    pub fn from_data<T: QuerySchema<Self>>(data: &T::Data) -> Self {
+        T::from_query_data(data)
+    }
+
+    /// Serializes like the type `T` generated from `improbable.ComponentInterest.Query`.
+    pub fn serialize<T: QuerySchema<Self>, O: SchemaObject>(
+        &self,
+        target: &mut O,
+    ) -> Result<(), EmptyOrError> {
+        T::data_serialize(&mut self.to_data::<T>()?, target);
+        Ok(())
+    }
+
+    pub fn deserialize<T: QuerySchema<Self>, O: SchemaObject>(source: &mut O) -> Self {
+        Self::from_data::<T>(&T::data_deserialize(source))
+    }
+
+    /// The equivalent entity query, when its constraints are ones entity queries support:
+    /// spheres, entity ids, components and their combinations.
+    #[cfg(feature = "sdk")]
+    pub fn to_entity_query(&self) -> Option<spatialos::worker::EntityQuery> {
+        Some(spatialos::worker::EntityQuery {
+            constraint: self.constraint.to_entity_query_constraint()?,
+            result_type: spatialos::worker::ResultType::Snapshot,
+            snapshot_result_type_component_ids: self
+                .result_component_ids
+                .clone()
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl From<Constraint> for Query {
+    fn from(constraint: Constraint) -> Self {
+        Self::new(constraint)
+    }
+}
+
+#[cfg(feature = "sdk")]
+impl Constraint {
+    fn to_entity_query_constraint(&self) -> Option<spatialos::worker::constraint::Constraint> {
+        use spatialos::worker::constraint::{
+            AndConstraint, ComponentConstraint, Constraint, EntityIdConstraint, OrConstraint,
+            SphereConstraint,
+        };
+        let constraint = match self {
+            Self::Sphere { center, radius } => Constraint::Sphere(SphereConstraint {
+                x: center[0],
+                y: center[1],
+                z: center[2],
+                radius: *radius,
+            }),
+            Self::EntityId(entity_id) => Constraint::EntityId(EntityIdConstraint {
+                entity_id: *entity_id,
+            }),
+            Self::Component(component_id) => Constraint::Component(ComponentConstraint {
+                component_id: *component_id,
+            }),
+            Self::And(constraints) => Constraint::And(AndConstraint {
+                constraints: constraints
+                    .iter()
+                    .map(Self::to_entity_query_constraint)
+                    .collect::<Option<_>>()?,
+            }),
+            Self::Or(constraints) => Constraint::Or(OrConstraint {
+                constraints: constraints
+                    .iter()
+                    .map(Self::to_entity_query_constraint)
+                    .collect::<Option<_>>()?,
+            }),
+            _ => return None,
+        };
+        Some(constraint)
+    }
+}
@@ -0,0 +1,85 @@
+//! The components linked into the binary, with the `registry` feature.
+//!
+//! Every `#[derive(SpatialComponent)]` registers itself at link time: [`components`] lists
+//! their ids, Rust and schema names, [`check_unique_ids`] reports two of them sharing an id
+//! and, with the `sdk` feature, `vtables` collects the vtables to pass to the connection.
+
+use std::fmt;
+
+#[cfg(feature = "sdk")]
+use spatialos::worker::component_vtable::ComponentVtable;
+
+/// Submitted by every `#[derive(SpatialComponent)]` when the `registry` feature is enabled.
+#[derive(Debug)]
+pub struct ComponentRegistration {
+    pub id: u32,
+    pub rust_name: &'static str,
+    pub schema_name: &'static str,
+    #[cfg(feature = "sdk")]
+    pub vtable: fn() -> ComponentVtable,
+}
+
+inventory::collect!(ComponentRegistration);
+
+#[derive(Debug, Clone, Copy)]
+pub struct DuplicateComponentId {
+    pub id: u32,
+    pub first: &'static ComponentRegistration,
+    pub second: &'static ComponentRegistration,
+}
+
+impl fmt::Display for DuplicateComponentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "components {} and {} share the id {}",
+            self.first.rust_name, self.second.rust_name, self.id
+        )
+    }
+}
+
+impl std::error::Error for DuplicateComponentId {}
+
+/// Every component linked into the binary, sorted by id.
+pub fn components() -> Vec<&'static ComponentRegistration> {
+    let mut components = inventory::iter::<ComponentRegistration>
+        .into_iter()
+        .collect::<Vec<_>>();
+    components.sort_by(|left, right| (left.id, left.rust_name).cmp(&(right.id, right.rust_name)));
+    components
+}
+
+pub fn find_component(id: u32) -> Option<&'static ComponentRegistration> {
+    inventory::iter::<ComponentRegistration>
+        .into_iter()
+        .find(|component| component.id == id)
+}
+
+/// Returns every pair of components sharing an id.
+pub fn find_duplicate_ids() -> Vec<DuplicateComponentId> {
+    components()
+        .windows(2)
+        .filter(|pair| pair[0].id == pair[1].id)
+        .map(|pair| DuplicateComponentId {
+            id: pair[0].id,
+            first: pair[0],
+            second: pair[1],
+        })
+        .collect()
+}
+
+pub fn check_unique_ids() -> Result<(), DuplicateComponentId> {
+    match find_duplicate_ids().into_iter().next() {
+        Some(duplicate) => Err(duplicate),
+        None => Ok(()),
+    }
+}
+
+/// The vtables of every registered component, to pass in the connection parameters.
+#[cfg(feature = "sdk")]
+pub fn vtables() -> Vec<ComponentVtable> {
+    components()
+        .into_iter()
+        .map(|component| (component.vtable)())
+        .collect()
+}
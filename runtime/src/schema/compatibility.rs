@@ -0,0 +1,44 @@
+use super::FieldId;
+
+/// A field added in a later version of a type, with `#[spatial(since = N)]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddedField {
+    pub field_id: FieldId,
+    pub since: u32,
+}
+
+/// Fields added and removed over the versions of a type, which deserializers tolerate missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compatibility {
+    pub added: &'static [AddedField],
+    /// Fields marked `#[spatial(deprecated)]`.
+    pub removed: &'static [FieldId],
+    /// Whether removed fields are no longer serialized, with `#[spatial(omit_deprecated)]`.
+    pub omits_removed: bool,
+}
+
+impl Compatibility {
+    pub const EMPTY: Self = Self {
+        added: &[],
+        removed: &[],
+        omits_removed: false,
+    };
+
+    /// Fields unknown to a reader built against `version`.
+    pub fn added_after(&self, version: u32) -> impl Iterator<Item = FieldId> + '_ {
+        self.added
+            .iter()
+            .filter(move |field| field.since > version)
+            .map(|field| field.field_id)
+    }
+
+    pub fn is_removed(&self, field_id: FieldId) -> bool {
+        self.removed.contains(&field_id)
+    }
+}
+
+impl Default for Compatibility {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
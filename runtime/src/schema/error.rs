@@ -0,0 +1,152 @@
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::str::Utf8Error;
+
+use super::{FieldId, SchemaObject, SchemaType};
+
+/// A field of a `#[spatial(strict)]` type whose number of values is not allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldCountError {
+    pub type_name: &'static str,
+    pub field: &'static str,
+    pub field_id: FieldId,
+    pub count: u32,
+}
+
+impl fmt::Display for FieldCountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.count == 0 {
+            write!(
+                f,
+                "{}: missing required field {} (id {})",
+                self.type_name, self.field, self.field_id
+            )
+        } else {
+            write!(
+                f,
+                "{}: field {} (id {}) has {} values",
+                self.type_name, self.field, self.field_id, self.count
+            )
+        }
+    }
+}
+
+impl std::error::Error for FieldCountError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaErrorKind {
+    InvalidEnum(u32),
+    InvalidString(Utf8Error),
+    FieldCount(FieldCountError),
+    /// A field id not declared by a `#[spatial(strict)]` type.
+    UnknownField(FieldId),
+}
+
+impl fmt::Display for SchemaErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEnum(value) => write!(f, "invalid enum value {}", value),
+            Self::InvalidString(error) => write!(f, "invalid string: {}", error),
+            Self::FieldCount(error) => error.fmt(f),
+            Self::UnknownField(field_id) => write!(f, "unknown field id {}", field_id),
+        }
+    }
+}
+
+/// An error of the fallible deserializers, with the path of the field it happened in,
+/// e.g. `improbable.Position.coords.x`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub path: Vec<String>,
+    pub kind: SchemaErrorKind,
+}
+
+impl SchemaError {
+    pub fn new(kind: SchemaErrorKind) -> Self {
+        Self {
+            path: Vec::new(),
+            kind,
+        }
+    }
+
+    /// Prepends `name` to the path of the error.
+    pub fn at(mut self, name: &str) -> Self {
+        self.path.insert(0, name.to_owned());
+        self
+    }
+
+    pub fn path(&self) -> String {
+        self.path.join(".")
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            self.kind.fmt(f)
+        } else {
+            write!(f, "{}: {}", self.path(), self.kind)
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl From<FieldCountError> for SchemaError {
+    fn from(error: FieldCountError) -> Self {
+        Self::new(SchemaErrorKind::FieldCount(error))
+    }
+}
+
+/// Runs a fallible deserializer of `T` without unwinding, as needed by the vtable entry points
+/// called from C, which can't return an error to the SDK. A failure is logged and then
+/// replaced by the default value if `T` opts in with `#[spatial(default_on_error)]`, while the
+/// process is aborted otherwise, rather than handing made-up data to the worker.
+pub fn recover<T, D, F>(deserialize: F) -> D
+where
+    T: SchemaType,
+    D: Default,
+    F: FnOnce() -> Result<D, SchemaError>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(deserialize)) {
+        Ok(Ok(value)) => return value,
+        Ok(Err(error)) => log::error!("Failed to deserialize {}", error),
+        Err(_) => log::error!("Panicked while deserializing {}", T::SCHEMA_NAME),
+    }
+    if T::DEFAULT_ON_ERROR {
+        D::default()
+    } else {
+        process::abort()
+    }
+}
+
+/// Runs a serializer of `T` without unwinding, like [`recover`]: a panic, e.g. in the function
+/// of a `with` adapter, is logged and then, if `T` opts in with `#[spatial(default_on_error)]`,
+/// the default value is written in place of the half written `target`. The process is
+/// aborted otherwise.
+pub fn recover_serialize<T, D, O, F>(data: &mut D, target: &mut O, serialize: F)
+where
+    T: SchemaType,
+    D: Default,
+    O: SchemaObject,
+    F: Fn(&mut D, &mut O),
+{
+    if panic::catch_unwind(AssertUnwindSafe(|| serialize(data, target))).is_ok() {
+        return;
+    }
+    log::error!("Panicked while serializing {}", T::SCHEMA_NAME);
+    if T::DEFAULT_ON_ERROR {
+        target.clear();
+        let serialized =
+            panic::catch_unwind(AssertUnwindSafe(|| serialize(&mut D::default(), target)));
+        if serialized.is_ok() {
+            return;
+        }
+        log::error!(
+            "Panicked while serializing the default value of {}",
+            T::SCHEMA_NAME
+        );
+    }
+    process::abort()
+}
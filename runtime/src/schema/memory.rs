@@ -0,0 +1,335 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use super::{for_each_primitive, EntityId, FieldId, SchemaObject};
+
+mod wire;
+
+pub(crate) use wire::read_varint;
+#[cfg(feature = "snapshot")]
+pub(crate) use wire::write_varint;
+pub use wire::WireError;
+
+#[derive(Debug, Clone)]
+enum Value {
+    Bool(bool),
+    Float(f32),
+    Double(f64),
+    Int32(i32),
+    Int64(i64),
+    Uint32(u32),
+    Uint64(u64),
+    Sint32(i32),
+    Sint64(i64),
+    Fixed32(u32),
+    Fixed64(u64),
+    Sfixed32(i32),
+    Sfixed64(i64),
+    EntityId(EntityId),
+    Enum(u32),
+    Bytes(Vec<u8>),
+    Object(MemoryObject),
+    /// Values read from a buffer, whose schema type is only known once they are accessed.
+    Varint(u64),
+    Bits32(u32),
+    Bits64(u64),
+    Delimited(Vec<u8>),
+}
+
+/// The schema type a field is accessed as, to decode the values read from a buffer.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Bool,
+    Float,
+    Double,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    EntityId,
+    Enum,
+    Bytes,
+}
+
+impl Kind {
+    fn value_of(self, number: u64) -> Value {
+        match self {
+            Self::Bool => Value::Bool(number != 0),
+            Self::Float => Value::Float(f32::from_bits(number as u32)),
+            Self::Double => Value::Double(f64::from_bits(number)),
+            Self::Int32 => Value::Int32(number as i32),
+            Self::Int64 => Value::Int64(number as i64),
+            Self::Uint32 => Value::Uint32(number as u32),
+            Self::Uint64 => Value::Uint64(number),
+            Self::Sint32 => Value::Sint32(wire::unzigzag(number) as i32),
+            Self::Sint64 => Value::Sint64(wire::unzigzag(number)),
+            Self::Fixed32 => Value::Fixed32(number as u32),
+            Self::Fixed64 => Value::Fixed64(number),
+            Self::Sfixed32 => Value::Sfixed32(number as u32 as i32),
+            Self::Sfixed64 => Value::Sfixed64(number as i64),
+            Self::EntityId => Value::EntityId(number as i64),
+            Self::Enum => Value::Enum(number as u32),
+            // Left as read, for the bytes accessors to skip it
+            Self::Bytes => Value::Varint(number),
+        }
+    }
+
+    /// Decodes a value read from a buffer, lists of numbers possibly being packed.
+    fn decode(self, value: &Value) -> Vec<Value> {
+        match value {
+            Value::Varint(number) | Value::Bits64(number) => vec![self.value_of(*number)],
+            Value::Bits32(bits) => vec![self.value_of(u64::from(*bits))],
+            Value::Delimited(bytes) => match self {
+                Self::Bytes => vec![Value::Bytes(bytes.clone())],
+                Self::Float | Self::Fixed32 | Self::Sfixed32 => bytes
+                    .chunks_exact(4)
+                    .map(|chunk| {
+                        let bits = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+                        self.value_of(u64::from(bits))
+                    })
+                    .collect(),
+                Self::Double | Self::Fixed64 | Self::Sfixed64 => bytes
+                    .chunks_exact(8)
+                    .map(|chunk| {
+                        let mut bits = [0; 8];
+                        bits.copy_from_slice(chunk);
+                        self.value_of(u64::from_le_bytes(bits))
+                    })
+                    .collect(),
+                _ => {
+                    let mut numbers = Vec::new();
+                    let mut position = 0;
+                    while let Ok(number) = read_varint(bytes, &mut position) {
+                        numbers.push(self.value_of(number));
+                    }
+                    numbers
+                }
+            },
+            value => vec![value.clone()],
+        }
+    }
+}
+
+/// A pure-Rust schema object, used to run the expanded code without the native SDK.
+///
+/// Like the native objects, the handles returned by `get_object`, `add_object` and
+/// `index_object` share their storage with the parent object.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryObject {
+    fields: Rc<RefCell<BTreeMap<FieldId, Vec<Value>>>>,
+}
+
+impl MemoryObject {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ids of the fields holding at least one value, in ascending order.
+    pub fn field_ids(&self) -> Vec<FieldId> {
+        self.fields.borrow().keys().copied().collect()
+    }
+
+    pub fn clear_field(&mut self, field_id: FieldId) {
+        self.fields.borrow_mut().remove(&field_id);
+    }
+
+    fn values<T, F: Fn(&Value) -> Option<T>>(
+        &self,
+        field_id: FieldId,
+        kind: Kind,
+        extract: F,
+    ) -> Vec<T> {
+        self.fields
+            .borrow()
+            .get(&field_id)
+            .map(|values| {
+                values
+                    .iter()
+                    .flat_map(|value| kind.decode(value))
+                    .filter_map(|value| extract(&value))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn enum_values(&self, field_id: FieldId) -> Vec<u32> {
+        self.values(field_id, Kind::Enum, |value| match value {
+            Value::Enum(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    /// Nested objects read from a buffer are decoded in place, for the handles to share
+    /// their storage.
+    fn objects(&self, field_id: FieldId) -> Vec<MemoryObject> {
+        let mut fields = self.fields.borrow_mut();
+        let values = match fields.get_mut(&field_id) {
+            Some(values) => values,
+            None => return Vec::new(),
+        };
+        for value in values.iter_mut() {
+            if let Value::Delimited(bytes) = value {
+                if let Ok(object) = MemoryObject::from_buffer(bytes) {
+                    *value = Value::Object(object);
+                }
+            }
+        }
+        values
+            .iter()
+            .filter_map(|value| match value {
+                Value::Object(object) => Some(object.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn push(&mut self, field_id: FieldId, value: Value) {
+        self.fields
+            .borrow_mut()
+            .entry(field_id)
+            .or_default()
+            .push(value)
+    }
+}
+
+macro_rules! implement_primitives {
+    ($($ty:ty, $variant:ident, $get:ident, $count:ident, $list:ident, $optional_list:ident, $index:ident, $add:ident, $add_list:ident;)*) => {
+        $(
+            fn $get(&self, field_id: FieldId) -> $ty {
+                self.$list(field_id).pop().unwrap_or_default()
+            }
+            fn $count(&self, field_id: FieldId) -> u32 {
+                self.$list(field_id).len() as u32
+            }
+            fn $list(&self, field_id: FieldId) -> Vec<$ty> {
+                self.values(field_id, Kind::$variant, |value| match value {
+                    Value::$variant(value) => Some(*value),
+                    _ => None,
+                })
+            }
+            fn $index(&self, field_id: FieldId, index: u32) -> $ty {
+                self.$list(field_id)
+                    .get(index as usize)
+                    .copied()
+                    .unwrap_or_default()
+            }
+            fn $add<D: std::borrow::Borrow<$ty>>(&mut self, field_id: FieldId, value: D) {
+                self.push(field_id, Value::$variant(*value.borrow()))
+            }
+            fn $add_list(&mut self, field_id: FieldId, values: &[$ty]) {
+                for value in values {
+                    self.push(field_id, Value::$variant(*value))
+                }
+            }
+        )*
+    };
+}
+
+impl SchemaObject for MemoryObject {
+    fn unique_field_ids(&self) -> Option<Vec<FieldId>> {
+        Some(self.field_ids())
+    }
+
+    fn clear(&mut self) {
+        self.fields.borrow_mut().clear()
+    }
+
+    for_each_primitive!(implement_primitives);
+
+    fn get_bytes(&self, field_id: FieldId) -> Vec<u8> {
+        self.get_bytes_list(field_id).pop().unwrap_or_default()
+    }
+
+    fn get_bytes_count(&self, field_id: FieldId) -> u32 {
+        self.get_bytes_list(field_id).len() as u32
+    }
+
+    fn get_bytes_list(&self, field_id: FieldId) -> Vec<Vec<u8>> {
+        self.values(field_id, Kind::Bytes, |value| match value {
+            Value::Bytes(bytes) => Some(bytes.clone()),
+            _ => None,
+        })
+    }
+
+    fn index_bytes(&self, field_id: FieldId, index: u32) -> Vec<u8> {
+        self.get_bytes_list(field_id)
+            .into_iter()
+            .nth(index as usize)
+            .unwrap_or_default()
+    }
+
+    fn add_bytes(&mut self, field_id: FieldId, value: &[u8]) {
+        self.push(field_id, Value::Bytes(value.to_vec()))
+    }
+
+    fn get_enum<E: From<u32>>(&self, field_id: FieldId) -> E {
+        E::from(self.enum_values(field_id).pop().unwrap_or_default())
+    }
+
+    fn get_enum_count(&self, field_id: FieldId) -> u32 {
+        self.enum_values(field_id).len() as u32
+    }
+
+    fn get_enum_list<E: From<u32>>(&self, field_id: FieldId) -> Vec<E> {
+        self.enum_values(field_id)
+            .into_iter()
+            .map(E::from)
+            .collect()
+    }
+
+    fn index_enum<E: From<u32>>(&self, field_id: FieldId, index: u32) -> E {
+        E::from(
+            self.enum_values(field_id)
+                .get(index as usize)
+                .copied()
+                .unwrap_or_default(),
+        )
+    }
+
+    fn add_enum<E>(&mut self, field_id: FieldId, value: &E)
+    where
+        for<'a> &'a E: Into<u32>,
+    {
+        self.push(field_id, Value::Enum(value.into()))
+    }
+
+    fn add_enum_list<E>(&mut self, field_id: FieldId, values: &[E])
+    where
+        for<'a> &'a E: Into<u32>,
+    {
+        for value in values {
+            self.add_enum(field_id, value)
+        }
+    }
+
+    fn get_object(&mut self, field_id: FieldId) -> Self {
+        match self.objects(field_id).pop() {
+            Some(object) => object,
+            None => self.add_object(field_id),
+        }
+    }
+
+    fn get_object_count(&self, field_id: FieldId) -> u32 {
+        self.objects(field_id).len() as u32
+    }
+
+    fn index_object(&mut self, field_id: FieldId, index: u32) -> Self {
+        self.objects(field_id)
+            .into_iter()
+            .nth(index as usize)
+            .unwrap_or_default()
+    }
+
+    fn add_object(&mut self, field_id: FieldId) -> Self {
+        let object = Self::new();
+        self.push(field_id, Value::Object(object.clone()));
+        object
+    }
+}
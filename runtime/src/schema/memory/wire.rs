@@ -0,0 +1,162 @@
+use std::fmt;
+
+use super::{MemoryObject, Value};
+use crate::schema::FieldId;
+
+const WIRE_VARINT: u64 = 0;
+const WIRE_BITS64: u64 = 1;
+const WIRE_DELIMITED: u64 = 2;
+const WIRE_BITS32: u64 = 5;
+
+/// A buffer which doesn't hold a serialized object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireError {
+    Truncated,
+    InvalidWireType(u8),
+    InvalidFieldId(u64),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "truncated buffer"),
+            Self::InvalidWireType(wire_type) => write!(f, "invalid wire type {}", wire_type),
+            Self::InvalidFieldId(field_id) => write!(f, "invalid field id {}", field_id),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+pub(crate) fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+pub(crate) fn read_varint(buffer: &[u8], position: &mut usize) -> Result<u64, WireError> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *buffer.get(*position).ok_or(WireError::Truncated)?;
+        *position += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte < 0x80 {
+            return Ok(value);
+        }
+    }
+    Err(WireError::Truncated)
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+pub(super) fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+fn read_bytes<'a>(
+    buffer: &'a [u8],
+    position: &mut usize,
+    length: usize,
+) -> Result<&'a [u8], WireError> {
+    let end = position
+        .checked_add(length)
+        .filter(|end| *end <= buffer.len())
+        .ok_or(WireError::Truncated)?;
+    let bytes = &buffer[*position..end];
+    *position = end;
+    Ok(bytes)
+}
+
+fn write_value(buffer: &mut Vec<u8>, field_id: FieldId, value: &Value) {
+    let tag = u64::from(field_id) << 3;
+    let varint = |buffer: &mut Vec<u8>, number: u64| {
+        write_varint(buffer, tag | WIRE_VARINT);
+        write_varint(buffer, number);
+    };
+    let bits32 = |buffer: &mut Vec<u8>, bits: u32| {
+        write_varint(buffer, tag | WIRE_BITS32);
+        buffer.extend_from_slice(&bits.to_le_bytes());
+    };
+    let bits64 = |buffer: &mut Vec<u8>, bits: u64| {
+        write_varint(buffer, tag | WIRE_BITS64);
+        buffer.extend_from_slice(&bits.to_le_bytes());
+    };
+    let delimited = |buffer: &mut Vec<u8>, bytes: &[u8]| {
+        write_varint(buffer, tag | WIRE_DELIMITED);
+        write_varint(buffer, bytes.len() as u64);
+        buffer.extend_from_slice(bytes);
+    };
+    match value {
+        Value::Bool(value) => varint(buffer, u64::from(*value)),
+        Value::Int32(value) => varint(buffer, i64::from(*value) as u64),
+        Value::Int64(value) | Value::EntityId(value) => varint(buffer, *value as u64),
+        Value::Uint32(value) | Value::Enum(value) => varint(buffer, u64::from(*value)),
+        Value::Uint64(value) | Value::Varint(value) => varint(buffer, *value),
+        Value::Sint32(value) => varint(buffer, zigzag(i64::from(*value))),
+        Value::Sint64(value) => varint(buffer, zigzag(*value)),
+        Value::Float(value) => bits32(buffer, value.to_bits()),
+        Value::Fixed32(value) | Value::Bits32(value) => bits32(buffer, *value),
+        Value::Sfixed32(value) => bits32(buffer, *value as u32),
+        Value::Double(value) => bits64(buffer, value.to_bits()),
+        Value::Fixed64(value) | Value::Bits64(value) => bits64(buffer, *value),
+        Value::Sfixed64(value) => bits64(buffer, *value as u64),
+        Value::Bytes(bytes) | Value::Delimited(bytes) => delimited(buffer, bytes),
+        Value::Object(object) => delimited(buffer, &object.serialize_to_buffer()),
+    }
+}
+
+/// Objects are serialized like protobuf messages, the numbers of the fields being their ids.
+/// The schema types of the values read back are only known once they are accessed, so
+/// reading a serialized object through another type never fails.
+impl MemoryObject {
+    pub fn serialize_to_buffer(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        for (field_id, values) in self.fields.borrow().iter() {
+            for value in values {
+                write_value(&mut buffer, *field_id, value);
+            }
+        }
+        buffer
+    }
+
+    /// Appends the values serialized in `buffer` to the fields of the object.
+    pub fn merge_from_buffer(&mut self, buffer: &[u8]) -> Result<(), WireError> {
+        let mut position = 0;
+        while position < buffer.len() {
+            let tag = read_varint(buffer, &mut position)?;
+            let field_id = tag >> 3;
+            if field_id == 0 || field_id > u64::from(FieldId::MAX) {
+                return Err(WireError::InvalidFieldId(field_id));
+            }
+            let value = match tag & 7 {
+                WIRE_VARINT => Value::Varint(read_varint(buffer, &mut position)?),
+                WIRE_BITS32 => {
+                    let bytes = read_bytes(buffer, &mut position, 4)?;
+                    Value::Bits32(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                }
+                WIRE_BITS64 => {
+                    let mut bits = [0; 8];
+                    bits.copy_from_slice(read_bytes(buffer, &mut position, 8)?);
+                    Value::Bits64(u64::from_le_bytes(bits))
+                }
+                WIRE_DELIMITED => {
+                    let length = read_varint(buffer, &mut position)? as usize;
+                    Value::Delimited(read_bytes(buffer, &mut position, length)?.to_vec())
+                }
+                wire_type => return Err(WireError::InvalidWireType(wire_type as u8)),
+            };
+            self.push(field_id as FieldId, value);
+        }
+        Ok(())
+    }
+
+    pub fn from_buffer(buffer: &[u8]) -> Result<Self, WireError> {
+        let mut object = Self::new();
+        object.merge_from_buffer(buffer)?;
+        Ok(object)
+    }
+}
@@ -0,0 +1,338 @@
+//! The schema object API targeted by the expanded code, and the traits implemented by the
+//! derives.
+//!
+//! [`SchemaObject`] is implemented for `spatialos::schema::Object` with the `sdk` feature and
+//! by `MemoryObject` with the `mock` feature, which encodes objects like protobuf messages
+//! through `serialize_to_buffer` and `from_buffer`.
+//!
+//! [`SchemaType::try_deserialize_data`] and [`SchemaType::try_deserialize_update`] check enum
+//! values and strings before deserializing, and return a [`SchemaError`] with the path of the
+//! offending field, e.g. `improbable.Position.coords.x`. Fields added with
+//! `#[spatial(since = N)]` or removed with `#[spatial(deprecated)]` may be missing, and then
+//! take their default value; [`SchemaType::COMPATIBILITY`] lists them.
+//!
+//! `FooView::new(&mut object)` reads a `Foo` without deserializing it, each accessor reading
+//! its field when called. Lists and maps come back as iterators over the object and `type`
+//! fields as views of the nested object, while strings and bytes come back as `Cow`s, invalid
+//! UTF-8 being replaced with `U+FFFD`. Accessors return schema-side values even for fields
+//! with an adapter, and `to_data()` deserializes the whole `FooData`.
+
+use std::borrow::{Borrow, Cow};
+use std::convert::TryFrom;
+
+mod compatibility;
+mod error;
+#[cfg(feature = "mock")]
+mod memory;
+#[cfg(feature = "sdk")]
+mod sdk;
+mod view;
+
+pub use compatibility::{AddedField, Compatibility};
+pub use error::{recover, recover_serialize, FieldCountError, SchemaError, SchemaErrorKind};
+#[cfg(feature = "snapshot")]
+pub(crate) use memory::{read_varint, write_varint};
+#[cfg(feature = "mock")]
+pub use memory::{MemoryObject, WireError};
+pub use view::{ObjectRef, SchemaView};
+
+pub type FieldId = u32;
+pub type EntityId = i64;
+
+pub const MAP_KEY_FIELD_ID: FieldId = 1;
+pub const MAP_VALUE_FIELD_ID: FieldId = 2;
+
+/// Calls `$callback` with one row per primitive schema type:
+/// `rust type, value variant, get, count, list, optional list, index, add, add list`
+macro_rules! for_each_primitive {
+    ($callback:ident) => {
+        $callback! {
+            bool, Bool, get_bool, get_bool_count, get_bool_list, get_optional_bool_list, index_bool, add_bool, add_bool_list;
+            f32, Float, get_float, get_float_count, get_float_list, get_optional_float_list, index_float, add_float, add_float_list;
+            f64, Double, get_double, get_double_count, get_double_list, get_optional_double_list, index_double, add_double, add_double_list;
+            i32, Int32, get_int32, get_int32_count, get_int32_list, get_optional_int32_list, index_int32, add_int32, add_int32_list;
+            i64, Int64, get_int64, get_int64_count, get_int64_list, get_optional_int64_list, index_int64, add_int64, add_int64_list;
+            u32, Uint32, get_uint32, get_uint32_count, get_uint32_list, get_optional_uint32_list, index_uint32, add_uint32, add_uint32_list;
+            u64, Uint64, get_uint64, get_uint64_count, get_uint64_list, get_optional_uint64_list, index_uint64, add_uint64, add_uint64_list;
+            i32, Sint32, get_sint32, get_sint32_count, get_sint32_list, get_optional_sint32_list, index_sint32, add_sint32, add_sint32_list;
+            i64, Sint64, get_sint64, get_sint64_count, get_sint64_list, get_optional_sint64_list, index_sint64, add_sint64, add_sint64_list;
+            u32, Fixed32, get_fixed32, get_fixed32_count, get_fixed32_list, get_optional_fixed32_list, index_fixed32, add_fixed32, add_fixed32_list;
+            u64, Fixed64, get_fixed64, get_fixed64_count, get_fixed64_list, get_optional_fixed64_list, index_fixed64, add_fixed64, add_fixed64_list;
+            i32, Sfixed32, get_sfixed32, get_sfixed32_count, get_sfixed32_list, get_optional_sfixed32_list, index_sfixed32, add_sfixed32, add_sfixed32_list;
+            i64, Sfixed64, get_sfixed64, get_sfixed64_count, get_sfixed64_list, get_optional_sfixed64_list, index_sfixed64, add_sfixed64, add_sfixed64_list;
+            EntityId, EntityId, get_entity_id, get_entity_id_count, get_entity_id_list, get_optional_entity_id_list, index_entity_id, add_entity_id, add_entity_id_list;
+        }
+    };
+}
+pub(crate) use for_each_primitive;
+
+macro_rules! declare_primitives {
+    ($($ty:ty, $variant:ident, $get:ident, $count:ident, $list:ident, $optional_list:ident, $index:ident, $add:ident, $add_list:ident;)*) => {
+        $(
+            fn $get(&self, field_id: FieldId) -> $ty;
+            fn $count(&self, field_id: FieldId) -> u32;
+            fn $list(&self, field_id: FieldId) -> Vec<$ty>;
+            fn $optional_list(&self, field_id: FieldId) -> Option<Vec<$ty>> {
+                if self.$count(field_id) > 0 {
+                    Some(self.$list(field_id))
+                } else {
+                    None
+                }
+            }
+            fn $index(&self, field_id: FieldId, index: u32) -> $ty;
+            fn $add<D: Borrow<$ty>>(&mut self, field_id: FieldId, value: D);
+            fn $add_list(&mut self, field_id: FieldId, values: &[$ty]);
+        )*
+    };
+}
+
+/// The subset of the schema object API targeted by the expanded code.
+///
+/// It is implemented for `spatialos::schema::Object` with the `sdk` feature
+/// and by [`MemoryObject`](struct.MemoryObject.html) with the `mock` feature.
+pub trait SchemaObject: Sized {
+    /// Ids of the fields holding at least one value, in ascending order, or `None` for
+    /// objects which can't list them, such as the SDK `Object` whose API doesn't expose them.
+    fn unique_field_ids(&self) -> Option<Vec<FieldId>> {
+        None
+    }
+
+    /// Removes every field of the object.
+    fn clear(&mut self);
+
+    for_each_primitive!(declare_primitives);
+
+    fn get_bytes(&self, field_id: FieldId) -> Vec<u8>;
+    fn get_bytes_count(&self, field_id: FieldId) -> u32;
+    fn index_bytes(&self, field_id: FieldId, index: u32) -> Vec<u8>;
+    fn add_bytes(&mut self, field_id: FieldId, value: &[u8]);
+
+    /// The bytes of the field, which objects able to lend them out may borrow. Neither the
+    /// SDK `Object`, whose bytes can't be borrowed through its API, nor `MemoryObject` does.
+    fn get_bytes_ref(&self, field_id: FieldId) -> Cow<'_, [u8]> {
+        Cow::Owned(self.get_bytes(field_id))
+    }
+
+    fn index_bytes_ref(&self, field_id: FieldId, index: u32) -> Cow<'_, [u8]> {
+        Cow::Owned(self.index_bytes(field_id, index))
+    }
+
+    fn get_bytes_list(&self, field_id: FieldId) -> Vec<Vec<u8>> {
+        (0..self.get_bytes_count(field_id))
+            .map(|index| self.index_bytes(field_id, index))
+            .collect()
+    }
+
+    fn get_optional_bytes_list(&self, field_id: FieldId) -> Option<Vec<Vec<u8>>> {
+        if self.get_bytes_count(field_id) > 0 {
+            Some(self.get_bytes_list(field_id))
+        } else {
+            None
+        }
+    }
+
+    fn add_bytes_list(&mut self, field_id: FieldId, values: &[Vec<u8>]) {
+        for value in values {
+            self.add_bytes(field_id, value)
+        }
+    }
+
+    fn get_string(&self, field_id: FieldId) -> String {
+        String::from_utf8(self.get_bytes(field_id)).unwrap()
+    }
+
+    fn get_string_count(&self, field_id: FieldId) -> u32 {
+        self.get_bytes_count(field_id)
+    }
+
+    fn index_string(&self, field_id: FieldId, index: u32) -> String {
+        String::from_utf8(self.index_bytes(field_id, index)).unwrap()
+    }
+
+    /// The string of the field, borrowed like [`get_bytes_ref`](Self::get_bytes_ref). Invalid
+    /// UTF-8 is replaced with `U+FFFD`, the fallible deserializers being the ones to reject it.
+    fn get_str(&self, field_id: FieldId) -> Cow<'_, str> {
+        bytes_to_str(self.get_bytes_ref(field_id))
+    }
+
+    fn index_str(&self, field_id: FieldId, index: u32) -> Cow<'_, str> {
+        bytes_to_str(self.index_bytes_ref(field_id, index))
+    }
+
+    fn add_string<S: AsRef<str>>(&mut self, field_id: FieldId, value: S) {
+        self.add_bytes(field_id, value.as_ref().as_bytes())
+    }
+
+    fn get_string_list(&self, field_id: FieldId) -> Vec<String> {
+        (0..self.get_string_count(field_id))
+            .map(|index| self.index_string(field_id, index))
+            .collect()
+    }
+
+    fn get_optional_string_list(&self, field_id: FieldId) -> Option<Vec<String>> {
+        if self.get_string_count(field_id) > 0 {
+            Some(self.get_string_list(field_id))
+        } else {
+            None
+        }
+    }
+
+    fn add_string_list(&mut self, field_id: FieldId, values: &[String]) {
+        for value in values {
+            self.add_string(field_id, value)
+        }
+    }
+
+    fn get_enum<E: From<u32>>(&self, field_id: FieldId) -> E;
+    fn get_enum_count(&self, field_id: FieldId) -> u32;
+    fn get_enum_list<E: From<u32>>(&self, field_id: FieldId) -> Vec<E>;
+    fn index_enum<E: From<u32>>(&self, field_id: FieldId, index: u32) -> E;
+    fn add_enum<E>(&mut self, field_id: FieldId, value: &E)
+    where
+        for<'a> &'a E: Into<u32>;
+    fn add_enum_list<E>(&mut self, field_id: FieldId, values: &[E])
+    where
+        for<'a> &'a E: Into<u32>;
+
+    fn get_object(&mut self, field_id: FieldId) -> Self;
+    fn get_object_count(&self, field_id: FieldId) -> u32;
+    fn index_object(&mut self, field_id: FieldId, index: u32) -> Self;
+    fn add_object(&mut self, field_id: FieldId) -> Self;
+}
+
+fn bytes_to_str(bytes: Cow<'_, [u8]>) -> Cow<'_, str> {
+    match bytes {
+        Cow::Borrowed(bytes) => String::from_utf8_lossy(bytes),
+        Cow::Owned(bytes) => match String::from_utf8(bytes) {
+            Ok(string) => Cow::Owned(string),
+            Err(error) => Cow::Owned(String::from_utf8_lossy(error.as_bytes()).into_owned()),
+        },
+    }
+}
+
+/// Implemented by `#[derive(SpatialType)]` and `#[derive(SpatialComponent)]`.
+#[diagnostic::on_unimplemented(message = "`{Self}` must derive SpatialType")]
+pub trait SchemaType {
+    type Data: Clone;
+    type Update: Clone;
+
+    /// Qualified name of the type in the schema, e.g. `improbable.Position`.
+    const SCHEMA_NAME: &'static str;
+
+    /// Fields added with `#[spatial(since = N)]` and removed with `#[spatial(deprecated)]`.
+    const COMPATIBILITY: Compatibility = Compatibility::EMPTY;
+
+    /// Whether the vtable entry points replace data which fails to deserialize, or to
+    /// serialize, with the default value, set with `#[spatial(default_on_error)]`. They abort
+    /// the process otherwise, since they can neither return the error to the SDK nor unwind
+    /// into it.
+    const DEFAULT_ON_ERROR: bool = false;
+
+    fn data_deserialize<O: SchemaObject>(source: &mut O) -> Self::Data;
+
+    fn data_serialize<O: SchemaObject>(data: &mut Self::Data, target: &mut O);
+
+    fn update_deserialize<O: SchemaObject>(source: &mut O) -> Self::Update;
+
+    fn update_serialize<O: SchemaObject>(data: &mut Self::Update, target: &mut O);
+
+    /// Checks the number of values of every field and rejects unknown field ids when the
+    /// object lists them, overridden with `#[spatial(strict)]`.
+    fn validate_data<O: SchemaObject>(_source: &mut O) -> Result<(), SchemaError> {
+        Ok(())
+    }
+
+    fn strict_data_deserialize<O: SchemaObject>(source: &mut O) -> Result<Self::Data, SchemaError> {
+        Self::validate_data(source)?;
+        Ok(Self::data_deserialize(source))
+    }
+
+    /// Checks that enum values are known and strings are valid UTF-8, in this type and
+    /// the types nested in it.
+    fn validate_values<O: SchemaObject>(source: &mut O) -> Result<(), SchemaError>;
+
+    fn try_deserialize_data<O: SchemaObject>(source: &mut O) -> Result<Self::Data, SchemaError> {
+        Self::validate_data(source)?;
+        Self::validate_values(source).map_err(|error| error.at(Self::SCHEMA_NAME))?;
+        Ok(Self::data_deserialize(source))
+    }
+
+    fn try_deserialize_update<O: SchemaObject>(
+        source: &mut O,
+    ) -> Result<Self::Update, SchemaError> {
+        Self::validate_values(source).map_err(|error| error.at(Self::SCHEMA_NAME))?;
+        Ok(Self::update_deserialize(source))
+    }
+}
+
+/// Boxed types are serialized like the type they box, for fields of a type referring to itself.
+impl<T: SchemaType> SchemaType for Box<T> {
+    type Data = Box<T::Data>;
+    type Update = Box<T::Update>;
+
+    const SCHEMA_NAME: &'static str = T::SCHEMA_NAME;
+
+    const COMPATIBILITY: Compatibility = T::COMPATIBILITY;
+
+    const DEFAULT_ON_ERROR: bool = T::DEFAULT_ON_ERROR;
+
+    fn data_deserialize<O: SchemaObject>(source: &mut O) -> Self::Data {
+        Box::new(T::data_deserialize(source))
+    }
+
+    fn data_serialize<O: SchemaObject>(data: &mut Self::Data, target: &mut O) {
+        T::data_serialize(data, target)
+    }
+
+    fn update_deserialize<O: SchemaObject>(source: &mut O) -> Self::Update {
+        Box::new(T::update_deserialize(source))
+    }
+
+    fn update_serialize<O: SchemaObject>(data: &mut Self::Update, target: &mut O) {
+        T::update_serialize(data, target)
+    }
+
+    fn validate_data<O: SchemaObject>(source: &mut O) -> Result<(), SchemaError> {
+        T::validate_data(source)
+    }
+
+    fn validate_values<O: SchemaObject>(source: &mut O) -> Result<(), SchemaError> {
+        T::validate_values(source)
+    }
+}
+
+/// Implemented by `#[derive(SpatialComponent)]`, the fields of a component are
+/// serialized with its `SchemaType` implementation.
+pub trait SchemaComponent: SchemaType {
+    const ID: u32;
+}
+
+/// Implemented by the `Data` struct of every `#[derive(SpatialComponent)]`, naming its component.
+pub trait SchemaComponentData: Sized {
+    type Component: SchemaComponent<Data = Self>;
+}
+
+/// Implemented by `#[derive(SpatialEnum)]`, lists the values of every variant in declaration order.
+#[diagnostic::on_unimplemented(message = "`{Self}` must derive SpatialEnum")]
+pub trait SchemaEnum: Sized + TryFrom<u32> {
+    const SCHEMA_NAME: &'static str;
+    const VALUES: &'static [u32];
+
+    /// The first value, which fields of the enum default to.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the enum has no value, a field of such an enum having nothing to hold.
+    fn first() -> Self {
+        Self::VALUES
+            .first()
+            .and_then(|value| Self::try_from(*value).ok())
+            .expect("an enum without values has no first value")
+    }
+
+    /// Reads a value of the enum, unknown values reading as the first one like missing fields
+    /// do, while `TryFrom` and the fallible deserializers reject them.
+    fn from_value(value: u32) -> Self {
+        Self::try_from(value).unwrap_or_else(|_| Self::first())
+    }
+}
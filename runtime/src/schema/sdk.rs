@@ -0,0 +1,99 @@
+use spatialos::schema::Object;
+use std::borrow::Borrow;
+
+use super::{for_each_primitive, EntityId, FieldId, SchemaObject};
+
+macro_rules! delegate_primitives {
+    ($($ty:ty, $variant:ident, $get:ident, $count:ident, $list:ident, $optional_list:ident, $index:ident, $add:ident, $add_list:ident;)*) => {
+        $(
+            fn $get(&self, field_id: FieldId) -> $ty {
+                Object::$get(self, field_id)
+            }
+            fn $count(&self, field_id: FieldId) -> u32 {
+                Object::$count(self, field_id)
+            }
+            fn $list(&self, field_id: FieldId) -> Vec<$ty> {
+                Object::$list(self, field_id)
+            }
+            fn $index(&self, field_id: FieldId, index: u32) -> $ty {
+                Object::$index(self, field_id, index)
+            }
+            fn $add<D: Borrow<$ty>>(&mut self, field_id: FieldId, value: D) {
+                Object::$add(self, field_id, value)
+            }
+            fn $add_list(&mut self, field_id: FieldId, values: &[$ty]) {
+                Object::$add_list(self, field_id, values)
+            }
+        )*
+    };
+}
+
+impl SchemaObject for Object {
+    fn clear(&mut self) {
+        Object::clear(self)
+    }
+
+    for_each_primitive!(delegate_primitives);
+
+    fn get_bytes(&self, field_id: FieldId) -> Vec<u8> {
+        Object::get_bytes(self, field_id)
+    }
+
+    fn get_bytes_count(&self, field_id: FieldId) -> u32 {
+        Object::get_bytes_count(self, field_id)
+    }
+
+    fn index_bytes(&self, field_id: FieldId, index: u32) -> Vec<u8> {
+        Object::index_bytes(self, field_id, index)
+    }
+
+    fn add_bytes(&mut self, field_id: FieldId, value: &[u8]) {
+        Object::add_bytes(self, field_id, value)
+    }
+
+    fn get_enum<E: From<u32>>(&self, field_id: FieldId) -> E {
+        Object::get_enum(self, field_id)
+    }
+
+    fn get_enum_count(&self, field_id: FieldId) -> u32 {
+        Object::get_enum_count(self, field_id)
+    }
+
+    fn get_enum_list<E: From<u32>>(&self, field_id: FieldId) -> Vec<E> {
+        Object::get_enum_list(self, field_id)
+    }
+
+    fn index_enum<E: From<u32>>(&self, field_id: FieldId, index: u32) -> E {
+        Object::index_enum(self, field_id, index)
+    }
+
+    fn add_enum<E>(&mut self, field_id: FieldId, value: &E)
+    where
+        for<'a> &'a E: Into<u32>,
+    {
+        Object::add_enum::<&E, E>(self, field_id, value)
+    }
+
+    fn add_enum_list<E>(&mut self, field_id: FieldId, values: &[E])
+    where
+        for<'a> &'a E: Into<u32>,
+    {
+        Object::add_enum_list(self, field_id, values)
+    }
+
+    fn get_object(&mut self, field_id: FieldId) -> Self {
+        Object::get_object(self, field_id)
+    }
+
+    fn get_object_count(&self, field_id: FieldId) -> u32 {
+        Object::get_object_count(self, field_id)
+    }
+
+    fn index_object(&mut self, field_id: FieldId, index: u32) -> Self {
+        Object::index_object(self, field_id, index)
+    }
+
+    fn add_object(&mut self, field_id: FieldId) -> Self {
+        Object::add_object(self, field_id)
+    }
+}
@@ -0,0 +1,47 @@
+use std::ops::{Deref, DerefMut};
+
+use super::{SchemaObject, SchemaType};
+
+/// The object read by a view, borrowed for the view of a component or type and owned by the
+/// views of nested objects, whose handles are returned by value by `get_object`.
+#[derive(Debug)]
+pub enum ObjectRef<'a, O> {
+    Borrowed(&'a mut O),
+    Owned(O),
+}
+
+impl<O> Deref for ObjectRef<'_, O> {
+    type Target = O;
+
+    fn deref(&self) -> &O {
+        match self {
+            Self::Borrowed(object) => object,
+            Self::Owned(object) => object,
+        }
+    }
+}
+
+impl<O> DerefMut for ObjectRef<'_, O> {
+    fn deref_mut(&mut self) -> &mut O {
+        match self {
+            Self::Borrowed(object) => object,
+            Self::Owned(object) => object,
+        }
+    }
+}
+
+/// Implemented by `#[derive(SpatialType)]` and `#[derive(SpatialComponent)]`, reading the
+/// fields of a schema object on access instead of deserializing it into `Data`.
+pub trait SchemaView: SchemaType {
+    type View<'a, O: SchemaObject + 'a>;
+
+    fn view<'a, O: SchemaObject + 'a>(object: ObjectRef<'a, O>) -> Self::View<'a, O>;
+}
+
+impl<T: SchemaView> SchemaView for Box<T> {
+    type View<'a, O: SchemaObject + 'a> = T::View<'a, O>;
+
+    fn view<'a, O: SchemaObject + 'a>(object: ObjectRef<'a, O>) -> Self::View<'a, O> {
+        T::view(object)
+    }
+}
@@ -0,0 +1,96 @@
+//! Helpers of the serde implementations generated with the `serde` feature, which derive
+//! `Serialize` and `Deserialize` on the `Data` and `Update` structs. `bytes` fields are
+//! encoded as base64 strings.
+
+use ::base64::engine::general_purpose::STANDARD;
+use ::base64::{DecodeError, Engine};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A field holding `bytes`, possibly inside a list, option or map, that is
+/// serialized as base64 strings.
+pub trait Base64: Sized {
+    type Encoded: Serialize + DeserializeOwned;
+
+    fn encode(&self) -> Self::Encoded;
+
+    fn decode(encoded: Self::Encoded) -> Result<Self, DecodeError>;
+}
+
+impl Base64 for Vec<u8> {
+    type Encoded = String;
+
+    fn encode(&self) -> Self::Encoded {
+        STANDARD.encode(self)
+    }
+
+    fn decode(encoded: Self::Encoded) -> Result<Self, DecodeError> {
+        STANDARD.decode(encoded)
+    }
+}
+
+impl<T: Base64> Base64 for Vec<T> {
+    type Encoded = Vec<T::Encoded>;
+
+    fn encode(&self) -> Self::Encoded {
+        self.iter().map(Base64::encode).collect()
+    }
+
+    fn decode(encoded: Self::Encoded) -> Result<Self, DecodeError> {
+        encoded.into_iter().map(Base64::decode).collect()
+    }
+}
+
+impl<T: Base64> Base64 for Option<T> {
+    type Encoded = Option<T::Encoded>;
+
+    fn encode(&self) -> Self::Encoded {
+        self.as_ref().map(Base64::encode)
+    }
+
+    fn decode(encoded: Self::Encoded) -> Result<Self, DecodeError> {
+        encoded.map(Base64::decode).transpose()
+    }
+}
+
+impl<K, V> Base64 for HashMap<K, V>
+where
+    K: Serialize + DeserializeOwned + Eq + Hash + Clone,
+    V: Base64,
+{
+    type Encoded = HashMap<K, V::Encoded>;
+
+    fn encode(&self) -> Self::Encoded {
+        self.iter()
+            .map(|(key, value)| (key.clone(), value.encode()))
+            .collect()
+    }
+
+    fn decode(encoded: Self::Encoded) -> Result<Self, DecodeError> {
+        encoded
+            .into_iter()
+            .map(|(key, value)| Ok((key, V::decode(value)?)))
+            .collect()
+    }
+}
+
+/// To be used with `#[serde(with = "spatialos_macro_runtime::serialization::base64")]`.
+pub mod base64 {
+    use super::*;
+
+    pub fn serialize<T: Base64, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.encode().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Base64, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let encoded = T::Encoded::deserialize(deserializer)?;
+        T::decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
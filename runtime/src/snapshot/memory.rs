@@ -0,0 +1,139 @@
+//! The snapshot format of [`MemorySnapshotWriter`], which doesn't need the Worker SDK.
+//!
+//! A snapshot starts with [`MAGIC`] and the varint [`VERSION`], followed by one record per
+//! entity: its varint id, the varint length of the entity and the entity itself, serialized
+//! like a schema object whose field ids are the ids of its components and whose values are
+//! the serialized fields of these components.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use super::{read_entity, registrations, SnapshotEntity, SnapshotError, SnapshotRegistration};
+use crate::schema::{read_varint, write_varint, EntityId, MemoryObject, SchemaObject, WireError};
+
+pub const MAGIC: &[u8; 8] = b"SPOSSNAP";
+pub const VERSION: u64 = 1;
+
+pub struct MemorySnapshotWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> MemorySnapshotWriter<W> {
+    pub fn new(mut writer: W) -> Result<Self, SnapshotError> {
+        let mut header = MAGIC.to_vec();
+        write_varint(&mut header, VERSION);
+        writer.write_all(&header)?;
+        Ok(Self { writer })
+    }
+
+    /// Writes the entity once its components pass the checks of its template, components
+    /// being written as they were read unless they changed.
+    pub fn write_entity(&mut self, entity: &mut SnapshotEntity) -> Result<(), SnapshotError> {
+        entity.check()?;
+        let mut object = MemoryObject::new();
+        for id in entity.component_ids() {
+            match entity.raw_components.get(&id) {
+                Some(fields) => object.add_bytes(id, fields),
+                None => {
+                    if let Some(fields) = entity.components.memory_object(id) {
+                        object.add_bytes(id, &fields.serialize_to_buffer());
+                    }
+                }
+            }
+        }
+        let buffer = object.serialize_to_buffer();
+        let mut record = Vec::with_capacity(buffer.len() + 16);
+        write_varint(&mut record, entity.entity_id as u64);
+        write_varint(&mut record, buffer.len() as u64);
+        record.extend_from_slice(&buffer);
+        self.writer.write_all(&record)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), SnapshotError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+pub struct MemorySnapshotReader<R: Read> {
+    reader: R,
+    registrations: HashMap<u32, &'static SnapshotRegistration>,
+}
+
+impl<R: Read> MemorySnapshotReader<R> {
+    /// Reads the header, failing as well when two registered components share an id.
+    pub fn new(mut reader: R) -> Result<Self, SnapshotError> {
+        let registrations = registrations()?;
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+        let version = read_stream_varint(&mut reader)?.ok_or(WireError::Truncated)?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        Ok(Self {
+            reader,
+            registrations,
+        })
+    }
+
+    /// Reads the next entity, `None` at the end of the snapshot.
+    pub fn read_entity(&mut self) -> Result<Option<SnapshotEntity>, SnapshotError> {
+        let entity_id = match read_stream_varint(&mut self.reader)? {
+            Some(entity_id) => entity_id as EntityId,
+            None => return Ok(None),
+        };
+        let length = read_stream_varint(&mut self.reader)?.ok_or(WireError::Truncated)?;
+        let mut buffer = vec![0; length as usize];
+        self.reader.read_exact(&mut buffer)?;
+        let object = MemoryObject::from_buffer(&buffer)?;
+        let components = object
+            .field_ids()
+            .into_iter()
+            .flat_map(|id| {
+                object
+                    .get_bytes_list(id)
+                    .into_iter()
+                    .map(move |fields| (id, fields))
+            })
+            .collect::<Vec<_>>();
+        read_entity(entity_id, components, &self.registrations).map(Some)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Iterator for MemorySnapshotReader<R> {
+    type Item = Result<SnapshotEntity, SnapshotError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entity().transpose()
+    }
+}
+
+/// Reads a varint from the stream, `None` when it ends before its first byte.
+fn read_stream_varint<R: Read>(reader: &mut R) -> Result<Option<u64>, SnapshotError> {
+    let mut bytes = Vec::new();
+    let mut byte = [0];
+    loop {
+        if reader.read(&mut byte)? == 0 {
+            if bytes.is_empty() {
+                return Ok(None);
+            }
+            return Err(WireError::Truncated.into());
+        }
+        bytes.push(byte[0]);
+        if byte[0] < 0x80 || bytes.len() == 10 {
+            return Ok(Some(read_varint(&bytes, &mut 0)?));
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Snapshot files of entities, their components being read and written with the serializers
+//! of the derived components.
+//!
+//! [`MemorySnapshotReader`] and [`MemorySnapshotWriter`] use a format of their own, without
+//! the SDK. Reading and writing the snapshot files of the Worker SDK is left to its snapshot
+//! streams, which aren't wrapped here.
+//!
+//! Components keep the bytes they were read from, which are written back unchanged until the
+//! component is changed, so that a snapshot read and written again loses nothing: the fields
+//! unknown to the derived components, and the components which don't decode, stay as they were.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::io;
+
+use crate::registry::{self, DuplicateComponentId};
+use crate::schema::{
+    EntityId, MemoryObject, SchemaComponent, SchemaComponentData, SchemaError, SchemaType,
+    WireError,
+};
+use crate::template::{EntityTemplate, TemplateError};
+
+mod memory;
+
+pub use memory::{MemorySnapshotReader, MemorySnapshotWriter, MAGIC, VERSION};
+
+/// Submitted by every `#[derive(SpatialComponent)]` when the `snapshot` feature is enabled.
+#[derive(Debug)]
+pub struct SnapshotRegistration {
+    pub id: u32,
+    pub read: fn(&mut EntityTemplate, &mut MemoryObject) -> Result<(), SnapshotError>,
+}
+
+inventory::collect!(SnapshotRegistration);
+
+/// Deserializes the fields of the component `C` and adds it to `components`.
+pub fn read_component<C: SchemaComponent + 'static>(
+    components: &mut EntityTemplate,
+    fields: &mut MemoryObject,
+) -> Result<(), SnapshotError>
+where
+    C::Data: 'static,
+{
+    let data = C::try_deserialize_data(fields)?;
+    components.add_component::<C>(data)?;
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    InvalidMagic,
+    UnsupportedVersion(u64),
+    Wire(WireError),
+    Schema(SchemaError),
+    Template(TemplateError),
+    DuplicateComponentId(DuplicateComponentId),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => error.fmt(f),
+            Self::InvalidMagic => write!(f, "not a snapshot"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            Self::Wire(error) => error.fmt(f),
+            Self::Schema(error) => error.fmt(f),
+            Self::Template(error) => error.fmt(f),
+            Self::DuplicateComponentId(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<WireError> for SnapshotError {
+    fn from(error: WireError) -> Self {
+        Self::Wire(error)
+    }
+}
+
+impl From<SchemaError> for SnapshotError {
+    fn from(error: SchemaError) -> Self {
+        Self::Schema(error)
+    }
+}
+
+impl From<TemplateError> for SnapshotError {
+    fn from(error: TemplateError) -> Self {
+        Self::Template(error)
+    }
+}
+
+impl From<DuplicateComponentId> for SnapshotError {
+    fn from(error: DuplicateComponentId) -> Self {
+        Self::DuplicateComponentId(error)
+    }
+}
+
+/// An entity of a snapshot, whose components are either decoded by the derived components
+/// registered in this binary or only kept as the serialized fields they were read from.
+#[derive(Debug)]
+pub struct SnapshotEntity {
+    pub entity_id: EntityId,
+    components: EntityTemplate,
+    /// Serialized fields of the components as read, dropped once a component is changed.
+    raw_components: BTreeMap<u32, Vec<u8>>,
+}
+
+impl SnapshotEntity {
+    /// An entity to write, whose components are serialized from `components`.
+    pub fn new(entity_id: EntityId, components: EntityTemplate) -> Self {
+        Self {
+            entity_id,
+            components,
+            raw_components: BTreeMap::new(),
+        }
+    }
+
+    /// The decoded components, which leave out the components that didn't decode.
+    pub fn components(&self) -> &EntityTemplate {
+        &self.components
+    }
+
+    pub fn get<C: SchemaComponent>(&self) -> Option<&C::Data>
+    where
+        C::Data: 'static,
+    {
+        self.components.get::<C>()
+    }
+
+    /// Changing the component drops the bytes it was read from, to serialize it again.
+    pub fn get_mut<C: SchemaComponent>(&mut self) -> Option<&mut C::Data>
+    where
+        C::Data: 'static,
+    {
+        let data = self.components.get_mut::<C>()?;
+        self.raw_components.remove(&C::ID);
+        Some(data)
+    }
+
+    /// Adds a component by its `Data` struct, failing when the entity already has its id.
+    pub fn add<D: SchemaComponentData + 'static>(&mut self, data: D) -> Result<(), TemplateError>
+    where
+        D::Component: 'static,
+    {
+        let id = <D::Component as SchemaComponent>::ID;
+        if self.raw_components.contains_key(&id) {
+            return Err(TemplateError::DuplicateComponent {
+                id,
+                schema_name: <D::Component as SchemaType>::SCHEMA_NAME,
+            });
+        }
+        self.components.add(data)
+    }
+
+    pub fn remove<C: SchemaComponent>(&mut self) -> bool {
+        let raw = self.raw_components.remove(&C::ID).is_some();
+        self.components.remove::<C>() || raw
+    }
+
+    /// The serialized fields the component was read from, `None` once it has been changed.
+    pub fn raw_component(&self, id: u32) -> Option<&[u8]> {
+        self.raw_components.get(&id).map(Vec::as_slice)
+    }
+
+    /// Ids of every component, decoded or not, in increasing order.
+    pub fn component_ids(&self) -> Vec<u32> {
+        self.components
+            .component_ids()
+            .chain(self.raw_components.keys().copied())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Checks the required components of the template, which may be kept undecoded.
+    fn check(&self) -> Result<(), TemplateError> {
+        self.components.check_present(|id| {
+            self.components.contains(id) || self.raw_components.contains_key(&id)
+        })
+    }
+}
+
+/// The snapshot registrations by component id, failing when two components share an id.
+fn registrations() -> Result<HashMap<u32, &'static SnapshotRegistration>, SnapshotError> {
+    registry::check_unique_ids()?;
+    Ok(inventory::iter::<SnapshotRegistration>
+        .into_iter()
+        .map(|registration| (registration.id, registration))
+        .collect())
+}
+
+/// Builds an entity from the serialized fields of its components, decoding the registered
+/// ones. A registered component which doesn't decode is only kept as its fields.
+fn read_entity(
+    entity_id: EntityId,
+    components: impl IntoIterator<Item = (u32, Vec<u8>)>,
+    registrations: &HashMap<u32, &'static SnapshotRegistration>,
+) -> Result<SnapshotEntity, SnapshotError> {
+    let mut entity = SnapshotEntity::new(entity_id, EntityTemplate::new());
+    for (id, fields) in components {
+        if entity.raw_components.contains_key(&id) {
+            return Err(TemplateError::DuplicateComponent {
+                id,
+                schema_name: registry::find_component(id)
+                    .map_or("unknown component", |component| component.schema_name),
+            }
+            .into());
+        }
+        if let Some(registration) = registrations.get(&id) {
+            let read = MemoryObject::from_buffer(&fields)
+                .map_err(SnapshotError::from)
+                .and_then(|mut object| (registration.read)(&mut entity.components, &mut object));
+            if let Err(error) = read {
+                log::warn!(
+                    "Keeping component {} of entity {} undecoded: {}",
+                    id,
+                    entity_id,
+                    error
+                );
+            }
+        }
+        entity.raw_components.insert(id, fields);
+    }
+    Ok(entity)
+}
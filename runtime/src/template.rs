@@ -0,0 +1,230 @@
+//! Entity templates assembling the components of an entity to create.
+//!
+//! [`EntityTemplate::add`] takes the `Data` struct of any derived component and rejects a
+//! second component with the same id. [`EntityTemplate::standard`] requires the components
+//! generated from `improbable.EntityAcl` and `improbable.Position`, and
+//! [`EntityTemplate::require`] adds more required components. With the `mock` feature,
+//! [`EntityTemplate::to_memory_objects`] checks them and serializes the fields of every
+//! component.
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+#[cfg(feature = "mock")]
+use crate::schema::MemoryObject;
+use crate::schema::{SchemaComponent, SchemaComponentData};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    DuplicateComponent { id: u32, schema_name: &'static str },
+    MissingComponent { id: u32, schema_name: &'static str },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateComponent { id, schema_name } => write!(
+                f,
+                "component {} (id {}) is already in the template",
+                schema_name, id
+            ),
+            Self::MissingComponent { id, schema_name } => {
+                write!(f, "missing required component {} (id {})", schema_name, id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// The `Data` of a component, serialized with the `SchemaType` implementation of `C`.
+trait TemplateComponent {
+    fn schema_name(&self) -> &'static str;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    #[cfg(feature = "mock")]
+    fn memory_object(&mut self) -> MemoryObject;
+}
+
+struct Entry<C: SchemaComponent> {
+    data: C::Data,
+    component: PhantomData<fn() -> C>,
+}
+
+impl<C: SchemaComponent + 'static> TemplateComponent for Entry<C>
+where
+    C::Data: 'static,
+{
+    fn schema_name(&self) -> &'static str {
+        C::SCHEMA_NAME
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        &self.data
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        &mut self.data
+    }
+
+    #[cfg(feature = "mock")]
+    fn memory_object(&mut self) -> MemoryObject {
+        let mut object = MemoryObject::new();
+        C::data_serialize(&mut self.data, &mut object);
+        object
+    }
+}
+
+/// The components of an entity to create, at most one per id, checked for the components
+/// every entity needs before being serialized.
+pub struct EntityTemplate {
+    components: BTreeMap<u32, Box<dyn TemplateComponent>>,
+    required: BTreeMap<u32, &'static str>,
+}
+
+impl EntityTemplate {
+    /// A template without required components.
+    pub fn new() -> Self {
+        Self {
+            components: BTreeMap::new(),
+            required: BTreeMap::new(),
+        }
+    }
+
+    /// A template requiring the components every entity needs, `Acl` and `Position` being the
+    /// components generated from `improbable.EntityAcl` and `improbable.Position`.
+    pub fn standard<Acl: SchemaComponent, Position: SchemaComponent>() -> Self {
+        Self::new().require::<Acl>().require::<Position>()
+    }
+
+    /// Requires the component `C` too.
+    pub fn require<C: SchemaComponent>(mut self) -> Self {
+        self.required.insert(C::ID, C::SCHEMA_NAME);
+        self
+    }
+
+    /// Adds a component by its `Data` struct, failing when the template already has its id.
+    pub fn add<D: SchemaComponentData + 'static>(&mut self, data: D) -> Result<(), TemplateError>
+    where
+        D::Component: 'static,
+    {
+        self.add_component::<D::Component>(data)
+    }
+
+    pub fn add_component<C: SchemaComponent + 'static>(
+        &mut self,
+        data: C::Data,
+    ) -> Result<(), TemplateError>
+    where
+        C::Data: 'static,
+    {
+        if let Some(component) = self.components.get(&C::ID) {
+            return Err(TemplateError::DuplicateComponent {
+                id: C::ID,
+                schema_name: component.schema_name(),
+            });
+        }
+        let entry = Entry::<C> {
+            data,
+            component: PhantomData,
+        };
+        self.components.insert(C::ID, Box::new(entry));
+        Ok(())
+    }
+
+    /// Same as `add`, for chaining.
+    pub fn with<D: SchemaComponentData + 'static>(mut self, data: D) -> Result<Self, TemplateError>
+    where
+        D::Component: 'static,
+    {
+        self.add(data)?;
+        Ok(self)
+    }
+
+    pub fn get<C: SchemaComponent>(&self) -> Option<&C::Data>
+    where
+        C::Data: 'static,
+    {
+        self.components
+            .get(&C::ID)
+            .and_then(|component| component.as_any().downcast_ref())
+    }
+
+    pub fn get_mut<C: SchemaComponent>(&mut self) -> Option<&mut C::Data>
+    where
+        C::Data: 'static,
+    {
+        self.components
+            .get_mut(&C::ID)
+            .and_then(|component| component.as_any_mut().downcast_mut())
+    }
+
+    pub fn remove<C: SchemaComponent>(&mut self) -> bool {
+        self.components.remove(&C::ID).is_some()
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.components.contains_key(&id)
+    }
+
+    /// Ids of the components, in increasing order.
+    pub fn component_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.components.keys().copied()
+    }
+
+    /// Checks that every required component has been added.
+    pub fn check(&self) -> Result<(), TemplateError> {
+        self.check_present(|id| self.components.contains_key(&id))
+    }
+
+    /// Checks that `is_present` holds for every required component.
+    pub(crate) fn check_present(
+        &self,
+        is_present: impl Fn(u32) -> bool,
+    ) -> Result<(), TemplateError> {
+        match self.required.iter().find(|(&id, _)| !is_present(id)) {
+            Some((&id, &schema_name)) => Err(TemplateError::MissingComponent { id, schema_name }),
+            None => Ok(()),
+        }
+    }
+
+    /// Serializes the fields of the component with the id, without checking the template.
+    #[cfg(feature = "mock")]
+    pub(crate) fn memory_object(&mut self, id: u32) -> Option<MemoryObject> {
+        Some(self.components.get_mut(&id)?.memory_object())
+    }
+
+    /// Serializes the fields of every component, in increasing order of id.
+    #[cfg(feature = "mock")]
+    pub fn to_memory_objects(&mut self) -> Result<Vec<(u32, MemoryObject)>, TemplateError> {
+        self.check()?;
+        Ok(self
+            .components
+            .iter_mut()
+            .map(|(&component_id, component)| (component_id, component.memory_object()))
+            .collect())
+    }
+}
+
+impl Default for EntityTemplate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for EntityTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.components
+                    .iter()
+                    .map(|(id, component)| (id, component.schema_name())),
+            )
+            .finish()
+    }
+}
@@ -0,0 +1,78 @@
+//! Strategies of the round-trip tests generated by `#[spatial(test_roundtrip)]`, with the
+//! `roundtrip` feature.
+
+use proptest::strategy::{BoxedStrategy, Just, NewTree, Strategy, ValueTree};
+use proptest::test_runner::TestRunner;
+use std::cell::Cell;
+use std::fmt::Debug;
+
+use crate::schema::SchemaType;
+
+/// Implemented by `#[spatial(test_roundtrip)]` types, every `type` field of such a
+/// type must point to a type with the same attribute.
+pub trait ArbitrarySchema: SchemaType {
+    fn arbitrary_data() -> BoxedStrategy<Self::Data>;
+
+    fn arbitrary_update() -> BoxedStrategy<Self::Update>;
+
+    /// Compares two values field by field, maps regardless of their iteration
+    /// order and floats by their bits so that `NaN` round-trips.
+    fn data_eq(left: &Self::Data, right: &Self::Data) -> bool;
+
+    fn update_eq(left: &Self::Update, right: &Self::Update) -> bool;
+}
+
+impl<T: ArbitrarySchema> ArbitrarySchema for Box<T>
+where
+    T::Data: Debug + 'static,
+    T::Update: Debug + 'static,
+{
+    fn arbitrary_data() -> BoxedStrategy<Self::Data> {
+        T::arbitrary_data().prop_map(Box::new).boxed()
+    }
+
+    fn arbitrary_update() -> BoxedStrategy<Self::Update> {
+        T::arbitrary_update().prop_map(Box::new).boxed()
+    }
+
+    fn data_eq(left: &Self::Data, right: &Self::Data) -> bool {
+        T::data_eq(left, right)
+    }
+
+    fn update_eq(left: &Self::Update, right: &Self::Update) -> bool {
+        T::update_eq(left, right)
+    }
+}
+
+const MAX_DEPTH: u32 = 3;
+
+thread_local! {
+    static DEPTH: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Builds the strategy of a list, option or map of types on demand and
+/// generates an empty value past a few levels, so recursive types terminate.
+#[derive(Debug)]
+pub struct Nested<T> {
+    make: fn() -> BoxedStrategy<T>,
+}
+
+pub fn nested<T>(make: fn() -> BoxedStrategy<T>) -> Nested<T> {
+    Nested { make }
+}
+
+impl<T: Clone + Debug + Default + 'static> Strategy for Nested<T> {
+    type Tree = Box<dyn ValueTree<Value = T>>;
+    type Value = T;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let depth = DEPTH.with(Cell::get);
+        if depth >= MAX_DEPTH {
+            return Just(T::default()).boxed().new_tree(runner);
+        }
+        DEPTH.with(|cell| cell.set(depth + 1));
+        let tree = (self.make)().new_tree(runner);
+        DEPTH.with(|cell| cell.set(depth));
+        tree
+    }
+}
@@ -1,60 +1,258 @@
 use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
 use syn::{Ident, ItemEnum, Variant};
 
-use super::get_value;
+use super::{get_schema_name, get_spatial_option_str, get_value, or_error};
 
 #[derive(Debug)]
 pub struct VariantAST {
     pub value: u32,
     pub name: Ident,
+    /// Name of the value in the schema, e.g. `AWAITING_WORKER_CONNECTION`, given with
+    /// `#[spatial(name = "...")]` and otherwise the name of the variant.
+    pub schema_name: String,
+    pub error: Option<syn::Error>,
 }
 
 impl From<&Variant> for VariantAST {
     fn from(input: &Variant) -> Self {
         let name = input.ident.clone();
         let value = get_value(&input.attrs).expect("Can't find value for variant");
-        Self { name, value }
+        let mut error = None;
+        let schema_name = or_error(get_spatial_option_str(&input.attrs, "name"), &mut error)
+            .unwrap_or_else(|| name.to_string());
+        Self {
+            name,
+            value,
+            schema_name,
+            error,
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct EnumAST<'a> {
     pub name: &'a Ident,
+    pub schema_name: String,
     pub variants: Vec<VariantAST>,
+    /// Invalid attribute of the enum, reported instead of expanding.
+    pub error: Option<syn::Error>,
 }
 
 impl<'a> From<&'a ItemEnum> for EnumAST<'a> {
     fn from(input: &'a ItemEnum) -> Self {
         let name = &input.ident;
+        let mut error = None;
+        let schema_name = or_error(get_schema_name(&input.attrs, name), &mut error);
         let variants = input
             .variants
             .iter()
             .map(VariantAST::from)
             .collect::<Vec<_>>();
-        Self { name, variants }
+        if let Some(variant_error) = variants.iter().find_map(|variant| variant.error.clone()) {
+            error.get_or_insert(variant_error);
+        }
+        Self {
+            name,
+            schema_name,
+            variants,
+            error,
+        }
+    }
+}
+
+impl EnumAST<'_> {
+    /// Unit variants are serialized by name, like `#[derive(Serialize, Deserialize)]` would.
+    fn get_impl_serde(&self) -> TokenStream2 {
+        if !cfg!(feature = "serde") {
+            return quote! {};
+        }
+        let name = self.name;
+        let enum_name = name.to_string();
+        let variant_names = self
+            .variants
+            .iter()
+            .map(|variant| variant.name.to_string())
+            .collect::<Vec<_>>();
+
+        let serialize = {
+            let variants = self
+                .variants
+                .iter()
+                .zip(&variant_names)
+                .enumerate()
+                .map(|(index, (variant, variant_name))| {
+                    let ident = &variant.name;
+                    let index = index as u32;
+                    quote! {
+                        #name :: #ident => serializer.serialize_unit_variant(#enum_name, #index, #variant_name)
+                    }
+                })
+                .collect::<Vec<_>>();
+            quote! {
+                impl spatialos_macro_runtime::serde::Serialize for #name {
+                    fn serialize<S: spatialos_macro_runtime::serde::Serializer>(
+                        &self,
+                        serializer: S,
+                    ) -> Result<S::Ok, S::Error> {
+                        match *self {
+                            #(#variants,)*
+                        }
+                    }
+                }
+            }
+        };
+
+        let deserialize = {
+            let variants = self
+                .variants
+                .iter()
+                .zip(&variant_names)
+                .map(|(variant, variant_name)| {
+                    let ident = &variant.name;
+                    quote! { #variant_name => Ok(#name :: #ident) }
+                })
+                .collect::<Vec<_>>();
+            quote! {
+                impl<'de> spatialos_macro_runtime::serde::Deserialize<'de> for #name {
+                    fn deserialize<D: spatialos_macro_runtime::serde::Deserializer<'de>>(
+                        deserializer: D,
+                    ) -> Result<Self, D::Error> {
+                        let variant = <String as spatialos_macro_runtime::serde::Deserialize>::deserialize(deserializer)?;
+                        match variant.as_str() {
+                            #(#variants,)*
+                            _ => Err(<D::Error as spatialos_macro_runtime::serde::de::Error>::unknown_variant(
+                                &variant,
+                                &[#(#variant_names,)*],
+                            )),
+                        }
+                    }
+                }
+            }
+        };
+
+        quote! {
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            #serialize
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            #deserialize
+        }
+    }
+}
+
+impl EnumAST<'_> {
+    /// Enums are written to snapshots as their numeric value.
+    fn get_impl_snapshot_json(&self) -> TokenStream2 {
+        if !cfg!(feature = "json") {
+            return quote! {};
+        }
+        let name = self.name;
+        quote! {
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl spatialos_macro_runtime::json::SnapshotJson for #name {
+                fn to_json(&self) -> spatialos_macro_runtime::json::Value {
+                    let value: u32 = self.into();
+                    spatialos_macro_runtime::json::Value::from(value)
+                }
+
+                fn from_json(
+                    value: &spatialos_macro_runtime::json::Value,
+                ) -> Result<Self, spatialos_macro_runtime::json::JsonError> {
+                    let value = <u32 as spatialos_macro_runtime::json::SnapshotJson>::from_json(value)?;
+                    <Self as std::convert::TryFrom<u32>>::try_from(value).map_err(|_| {
+                        spatialos_macro_runtime::json::JsonError::InvalidValue(format!(
+                            "{} is not a value of {}",
+                            value,
+                            stringify!(#name)
+                        ))
+                    })
+                }
+            }
+        }
+    }
+}
+
+impl EnumAST<'_> {
+    /// Values keep their schema name, e.g. `AWAITING_WORKER_CONNECTION`.
+    fn get_bundle_entry(&self) -> TokenStream2 {
+        let name = self.name;
+        let qualified_name = &self.schema_name;
+        let values = self
+            .variants
+            .iter()
+            .map(|variant| {
+                let value_name = &variant.schema_name;
+                let value = variant.value;
+                quote! {
+                    spatialos_macro_runtime::bundle::EnumValueDefinition {
+                        name: #value_name,
+                        value: #value,
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        let registration = if cfg!(feature = "registry") {
+            quote! {
+                spatialos_macro_runtime::inventory::submit! {
+                    spatialos_macro_runtime::bundle::BundleRegistration {
+                        entry: #name::schema_bundle_entry,
+                    }
+                }
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl #name {
+                pub fn schema_bundle_entry() -> spatialos_macro_runtime::bundle::BundleEntry {
+                    spatialos_macro_runtime::bundle::BundleEntry::Enum(
+                        spatialos_macro_runtime::bundle::EnumDefinition {
+                            qualified_name: #qualified_name,
+                            values: vec![#(#values,)*],
+                        },
+                    )
+                }
+            }
+
+            #registration
+        }
     }
 }
 
 impl<'a> Into<TokenStream> for EnumAST<'a> {
     fn into(self) -> TokenStream {
+        if let Some(error) = &self.error {
+            return error.to_compile_error().into();
+        }
+
         let name = self.name;
 
-        let from_u32 = {
+        let try_from_u32 = {
             let variants = self
                 .variants
                 .iter()
                 .map(|variant| {
                     let value = variant.value;
                     let ident = &variant.name;
-                    quote! { #value => #name :: #ident}
+                    quote! { #value => Ok(#name :: #ident) }
                 })
                 .collect::<Vec<_>>();
             quote! {
-                impl From<u32> for #name {
-                    fn from(data: u32) -> Self {
+                impl std::convert::TryFrom<u32> for #name {
+                    type Error = spatialos_macro_runtime::schema::SchemaError;
+
+                    fn try_from(data: u32) -> Result<Self, Self::Error> {
                         match data {
                             #(#variants,)*
-                            _ => panic!("Invalid data")
+                            _ => Err(spatialos_macro_runtime::schema::SchemaError::new(
+                                spatialos_macro_runtime::schema::SchemaErrorKind::InvalidEnum(data),
+                            )),
                         }
                     }
                 }
@@ -100,10 +298,29 @@ impl<'a> Into<TokenStream> for EnumAST<'a> {
             }
         };
 
+        let schema_enum = {
+            let values = self
+                .variants
+                .iter()
+                .map(|variant| variant.value)
+                .collect::<Vec<_>>();
+            let schema_name = &self.schema_name;
+            quote! {
+                impl spatialos_macro_runtime::schema::SchemaEnum for #name {
+                    const SCHEMA_NAME: &'static str = #schema_name;
+                    const VALUES: &'static [u32] = &[#(#values,)*];
+                }
+            }
+        };
+
+        let serde = self.get_impl_serde();
+        let snapshot_json = self.get_impl_snapshot_json();
+        let bundle_entry = self.get_bundle_entry();
+
         let result = quote! {
             #[automatically_derived]
             #[allow(unused_qualifications)]
-            #from_u32
+            #try_from_u32
 
             #[automatically_derived]
             #[allow(unused_qualifications)]
@@ -112,6 +329,16 @@ impl<'a> Into<TokenStream> for EnumAST<'a> {
             #[automatically_derived]
             #[allow(unused_qualifications)]
             #as_ref
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            #schema_enum
+
+            #serde
+
+            #snapshot_json
+
+            #bundle_entry
         };
         result.into()
     }
@@ -1,46 +1,153 @@
 use proc_macro2::TokenStream as TokenStream2;
-use syn::{Field, Ident};
+use syn::{spanned::Spanned, Expr, Field, Ident, Path, Type};
 
-use super::{get_field_id, SpatialType};
+use super::{
+    get_field_id, get_spatial_expr, get_spatial_option_lit_str, get_spatial_option_u32,
+    get_spatial_type, has_spatial_flag, or_error, SpatialType,
+};
+
+/// Converts the annotated type of a field from and to its schema type in the `Data` struct.
+#[derive(Debug)]
+pub enum Adapter {
+    /// `#[spatial(with = "module")]`, calling `module::serialize(&T)` and `module::deserialize`.
+    With(Path),
+    /// `#[spatial(into = "Type")]`, going through `Into<Type>` and `From<Type>`.
+    Into,
+}
 
 #[derive(Debug)]
 pub struct FieldAST {
     pub id: u32,
     pub name: Ident,
     pub ty: SpatialType,
+    pub default: Option<Expr>,
+    pub rust_type: Type,
+    pub adapter: Option<Adapter>,
+    pub since: Option<u32>,
+    pub deprecated: bool,
+    /// Invalid attribute or mismatch between the schema type and the declared type, reported
+    /// instead of expanding.
+    pub error: Option<syn::Error>,
 }
 
 impl FieldAST {
+    /// Fields with a default, added in a later version or deprecated may be missing from data.
+    pub fn is_tolerant(&self) -> bool {
+        self.default.is_some() || self.since.is_some() || self.deprecated
+    }
+
+    /// Boxes the references of the struct `name` to itself, a direct one being an error.
+    pub fn resolve_self_references(&mut self, name: &Ident) {
+        if self.ty.is_direct_self_reference(name) && self.error.is_none() {
+            self.error = Some(syn::Error::new(
+                self.rust_type.span(),
+                format!(
+                    "{} can't contain itself directly, use option<type> or list<type>",
+                    name
+                ),
+            ));
+        }
+        self.ty.resolve_self_references(name, false);
+    }
+
+    /// The annotated type when the field goes through an adapter, the schema type otherwise.
+    pub fn get_data_type(&self) -> Type {
+        match self.adapter {
+            Some(_) => self.rust_type.clone(),
+            None => self.ty.get_data_type(),
+        }
+    }
+
+    /// Converts the `Data` value at `place` to its schema type.
+    fn get_schema_value(&self, place: &TokenStream2) -> TokenStream2 {
+        let schema_type = self.ty.get_data_type();
+        match &self.adapter {
+            Some(Adapter::With(path)) => quote! { #path::serialize(&#place) },
+            Some(Adapter::Into) => quote! { Into::<#schema_type>::into(#place.clone()) },
+            None => place.clone(),
+        }
+    }
+
+    /// Converts the schema `value` to the type of the field in the `Data` struct.
+    fn get_data_value(&self, value: &TokenStream2) -> TokenStream2 {
+        let schema_type = self.ty.get_data_type();
+        let rust_type = &self.rust_type;
+        match &self.adapter {
+            Some(Adapter::With(path)) => quote! { #path::deserialize(#value) },
+            Some(Adapter::Into) => quote! { <#rust_type as From<#schema_type>>::from(#value) },
+            None => value.clone(),
+        }
+    }
+
+    fn get_serde_attributes(&self) -> TokenStream2 {
+        if cfg!(feature = "serde") && self.ty.contains_bytes() {
+            quote! { #[serde(with = "spatialos_macro_runtime::serialization::base64")] }
+        } else {
+            quote! {}
+        }
+    }
+
     pub fn get_update_field(&self) -> TokenStream2 {
         let name = &self.name;
         let utype = self.ty.get_update_type();
+        let attributes = self.get_serde_attributes();
         quote! {
+            #attributes
             pub #name: #utype
         }
     }
 
     pub fn get_data_field(&self) -> TokenStream2 {
         let name = &self.name;
-        let dtype = self.ty.get_data_type();
+        let dtype = self.get_data_type();
+        let attributes = match self.adapter {
+            Some(_) => quote! {},
+            None => self.get_serde_attributes(),
+        };
         quote! {
+            #attributes
             pub #name: #dtype
         }
     }
     pub fn get_data_deserializer(&self, object_name: &Ident) -> TokenStream2 {
         let id = self.id;
         let name = &self.name;
-        let deserializer = self.ty.get_data_deserializer(object_name, id);
-        quote! {
-            let #name = #deserializer;
+        let deserializer = self.get_data_value(&self.ty.get_data_deserializer(object_name, id));
+        if self.is_tolerant() && self.ty.is_singular() {
+            let count = format_ident!("get_{}_count", self.ty.get_accessor_name());
+            let default = self.get_data_default_value();
+            quote! {
+                let #name = if #object_name.#count(#id) > 0 {
+                    #deserializer
+                } else {
+                    #default
+                };
+            }
+        } else {
+            quote! {
+                let #name = #deserializer;
+            }
         }
     }
     pub fn get_data_serializer(&self, data: &Ident, target: &Ident) -> TokenStream2 {
         let id = self.id;
         let name = &self.name;
         let data = quote! { #data.#name };
-        let serializer = self.ty.get_data_serializer(&data, target, id);
+        if self.adapter.is_none() {
+            let serializer = self.ty.get_data_serializer(&data, target, id);
+            return quote! {
+                #serializer;
+            };
+        }
+        let value = format_ident!("value");
+        let schema_value = self.get_schema_value(&data);
+        let serializer = self.ty.get_data_serializer(&quote! { #value }, target, id);
         quote! {
-            #serializer;
+            {
+                #[allow(unused_mut)]
+                let mut #value = #schema_value;
+                #serializer;
+            }
         }
     }
     pub fn get_update_deserializer(&self, object_name: &Ident) -> TokenStream2 {
@@ -60,17 +167,383 @@ impl FieldAST {
             #serializer;
         }
     }
+
+    pub fn get_json_serializer(&self, data: &Ident, fields: &Ident) -> TokenStream2 {
+        let id = self.id.to_string();
+        let name = &self.name;
+        let schema_name = self.ty.get_schema_name();
+        let value = self.get_schema_value(&quote! { #data.#name });
+        quote! {
+            #fields.insert(
+                #id.to_owned(),
+                spatialos_macro_runtime::json::field(
+                    #schema_name,
+                    spatialos_macro_runtime::json::SnapshotJson::to_json(&#value),
+                ),
+            );
+        }
+    }
+
+    pub fn get_json_deserializer(&self, fields: &Ident) -> TokenStream2 {
+        let id = self.id;
+        let name = &self.name;
+        let schema_name = self.ty.get_schema_name();
+        let schema_type = self.ty.get_data_type();
+        if self.is_tolerant() {
+            let value = self.get_data_value(&quote! { value });
+            let default = self.get_data_default_value();
+            return quote! {
+                let #name = match spatialos_macro_runtime::json::get_optional_field::<#schema_type>(
+                    #fields,
+                    #id,
+                    #schema_name,
+                )? {
+                    Some(value) => #value,
+                    None => #default,
+                };
+            };
+        }
+        let value = self.get_data_value(&quote! {
+            spatialos_macro_runtime::json::get_field::<#schema_type>(#fields, #id, #schema_name)?
+        });
+        quote! {
+            let #name = #value;
+        }
+    }
+
+    /// Singular fields need exactly one value, or at most one with a default, like options.
+    pub fn get_validator(&self, object_name: &Ident) -> TokenStream2 {
+        let id = self.id;
+        let name = self.name.to_string();
+        let counted = match &self.ty {
+            SpatialType::Option(spatial_type) => Some(spatial_type.as_ref()),
+            spatial_type if spatial_type.is_singular() => Some(spatial_type),
+            _ => None,
+        };
+        let count_check = match counted {
+            Some(spatial_type) => {
+                let count = format_ident!("get_{}_count", spatial_type.get_accessor_name());
+                let invalid = if self.ty.is_singular() && !self.is_tolerant() {
+                    quote! { count != 1 }
+                } else {
+                    quote! { count > 1 }
+                };
+                quote! {
+                    let count = #object_name.#count(#id);
+                    if #invalid {
+                        return Err(spatialos_macro_runtime::schema::FieldCountError {
+                            type_name: <Self as spatialos_macro_runtime::schema::SchemaType>::SCHEMA_NAME,
+                            field: #name,
+                            field_id: #id,
+                            count,
+                        }
+                        .into());
+                    }
+                }
+            }
+            None => quote! {},
+        };
+        let nested = self.ty.get_nested_validator(object_name, id);
+        quote! {
+            #count_check
+            #nested
+        }
+    }
+
+    pub fn get_bundle_definition(&self) -> TokenStream2 {
+        let name = self.name.to_string();
+        let field_id = self.id;
+        let ty = self.ty.get_bundle_type();
+        quote! {
+            spatialos_macro_runtime::bundle::FieldDefinition {
+                name: #name,
+                field_id: #field_id,
+                ty: #ty,
+            }
+        }
+    }
+
+    /// Accessor of the view, returning the schema value even when the field has an adapter.
+    pub fn get_view_accessor(&self) -> TokenStream2 {
+        let id = self.id;
+        let name = &self.name;
+        let object = format_ident!("object");
+        let ty = self.ty.get_view_type();
+        let mut reader = self.ty.get_view_reader(&object, id);
+        if self.is_tolerant() && self.ty.is_singular() && !matches!(self.ty, SpatialType::Type(_)) {
+            let count = format_ident!("get_{}_count", self.ty.get_accessor_name());
+            let mut default = match &self.default {
+                Some(default) => self.get_schema_default(default),
+                None => self.ty.get_data_default(),
+            };
+            if matches!(self.ty, SpatialType::String | SpatialType::Bytes) {
+                default = quote! { std::borrow::Cow::Owned(#default) };
+            }
+            reader = quote! {
+                if #object.#count(#id) > 0 {
+                    #reader
+                } else {
+                    #default
+                }
+            };
+        }
+        if self.ty.is_view_mutable() {
+            quote! {
+                pub fn #name(&mut self) -> #ty {
+                    let #object = &mut *self.object;
+                    #reader
+                }
+            }
+        } else {
+            quote! {
+                pub fn #name(&self) -> #ty {
+                    let #object = &*self.object;
+                    #reader
+                }
+            }
+        }
+    }
+
+    pub fn get_value_validator(&self, object_name: &Ident) -> TokenStream2 {
+        self.ty
+            .get_value_validator(object_name, self.id, &self.name.to_string())
+    }
+
+    pub fn get_conversion(&self, source: &Ident) -> TokenStream2 {
+        let name = &self.name;
+        if self.adapter.is_some() {
+            return quote! { #name: #source.#name };
+        }
+        let conversion = self.ty.get_conversion(&quote! { #source.#name });
+        quote! { #name: #conversion }
+    }
+
+    /// Value of the field in an update moving the whole `Data`, or cloning its dirty fields.
+    pub fn get_data_update(&self, data: &TokenStream2, dirty: Option<&Ident>) -> TokenStream2 {
+        let id = self.id;
+        let name = &self.name;
+        let value = match (dirty, &self.adapter) {
+            (_, Some(_)) => self.get_schema_value(&quote! { #data.#name }),
+            (Some(_), None) => quote! { #data.#name.clone() },
+            (None, None) => quote! { #data.#name },
+        };
+        let conversion = self.ty.get_conversion(&value);
+        if !self.ty.is_update_optional() {
+            return quote! { #name: #conversion };
+        }
+        match dirty {
+            Some(dirty) => quote! {
+                #name: if #dirty.contains(&#id) {
+                    Some(#conversion)
+                } else {
+                    None
+                }
+            },
+            None => quote! { #name: Some(#conversion) },
+        }
+    }
+
+    /// Single-valued fields which may not be missing must be set by the `Data` builder.
+    pub fn is_required(&self) -> bool {
+        self.ty.is_singular() && !self.is_tolerant()
+    }
+
+    fn get_data_default_value(&self) -> TokenStream2 {
+        match &self.default {
+            Some(default) => quote! { #default },
+            None => self.get_data_value(&self.ty.get_data_default()),
+        }
+    }
+
+    pub fn get_data_default(&self) -> TokenStream2 {
+        let name = &self.name;
+        let default = self.get_data_default_value();
+        quote! { #name: #default }
+    }
+
+    /// The default of the field converted to its schema type, for the `Update` struct and the
+    /// view, which hold schema-side values.
+    fn get_schema_default(&self, default: &Expr) -> TokenStream2 {
+        if self.adapter.is_none() {
+            return quote! { #default };
+        }
+        let rust_type = &self.rust_type;
+        let value = self.get_schema_value(&quote! { value });
+        quote! {
+            {
+                let value: #rust_type = #default;
+                #value
+            }
+        }
+    }
+
+    pub fn get_update_default(&self) -> TokenStream2 {
+        let name = &self.name;
+        let default = match &self.default {
+            Some(default) if self.ty.is_singular() && !matches!(self.ty, SpatialType::Type(_)) => {
+                self.get_schema_default(default)
+            }
+            _ => self.ty.get_update_default(),
+        };
+        quote! { #name: #default }
+    }
+
+    pub fn get_data_strategy(&self) -> TokenStream2 {
+        let strategy = self.ty.get_data_strategy();
+        match self.adapter {
+            Some(_) => {
+                let value = self.get_data_value(&quote! { value });
+                quote! { #strategy.prop_map(|value| #value) }
+            }
+            None => strategy,
+        }
+    }
+
+    pub fn get_update_strategy(&self) -> TokenStream2 {
+        self.ty.get_update_strategy()
+    }
+
+    pub fn get_data_eq(&self, left: &Ident, right: &Ident) -> TokenStream2 {
+        let name = &self.name;
+        let eq = self.ty.get_data_eq(&quote! { #left }, &quote! { #right });
+        let left_value = self.get_schema_value(&quote! { #left.#name });
+        let right_value = self.get_schema_value(&quote! { #right.#name });
+        quote! {
+            {
+                let (#left, #right) = (&#left_value, &#right_value);
+                #eq
+            }
+        }
+    }
+
+    pub fn get_update_eq(&self, left: &Ident, right: &Ident) -> TokenStream2 {
+        let name = &self.name;
+        let eq = self.ty.get_update_eq(&quote! { #left }, &quote! { #right });
+        quote! {
+            {
+                let (#left, #right) = (&#left.#name, &#right.#name);
+                #eq
+            }
+        }
+    }
+}
+
+/// A `#[spatial(skip)]` field, only kept in the `Data` struct.
+#[derive(Debug)]
+pub struct SkippedFieldAST {
+    pub name: Ident,
+    pub ty: Type,
+    pub default: Option<Expr>,
+    pub error: Option<syn::Error>,
+}
+
+impl SkippedFieldAST {
+    pub fn get_data_field(&self) -> TokenStream2 {
+        let name = &self.name;
+        let ty = &self.ty;
+        let attributes = if cfg!(feature = "serde") {
+            quote! { #[serde(skip)] }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #attributes
+            pub #name: #ty
+        }
+    }
+
+    pub fn get_data_default(&self) -> TokenStream2 {
+        let name = &self.name;
+        match &self.default {
+            Some(default) => quote! { #name: #default },
+            None => quote! { #name: Default::default() },
+        }
+    }
+}
+
+impl From<&Field> for SkippedFieldAST {
+    fn from(field: &Field) -> Self {
+        let name = field
+            .ident
+            .as_ref()
+            .expect("Can't find field ident")
+            .clone();
+        let ty = field.ty.clone();
+        let mut error = None;
+        let default = or_error(get_spatial_expr(&field.attrs, "default"), &mut error);
+        Self {
+            name,
+            ty,
+            default,
+            error,
+        }
+    }
 }
 
 impl From<&Field> for FieldAST {
     fn from(field: &Field) -> Self {
-        let id = get_field_id(&field.attrs).expect("Can't find the field_id");
         let name = field
             .ident
             .as_ref()
             .expect("Can't find field ident")
             .clone();
-        let ty = SpatialType::from(field);
-        Self { id, name, ty }
+        let rust_type = field.ty.clone();
+        let mut error = None;
+        let id = get_field_id(&field.attrs).unwrap_or_else(|| {
+            error.get_or_insert(syn::Error::new(name.span(), "expected #[field_id(..)]"));
+            0
+        });
+        let schema_type = or_error(
+            get_spatial_option_lit_str(&field.attrs, "into")
+                .and_then(|into| into.map(|into| into.parse::<Type>()).transpose()),
+            &mut error,
+        );
+        let with = or_error(
+            get_spatial_option_lit_str(&field.attrs, "with")
+                .and_then(|with| with.map(|with| with.parse::<Path>()).transpose()),
+            &mut error,
+        );
+        let ty = match get_spatial_type(&field.attrs) {
+            Some(spatial_type) => {
+                SpatialType::from_syn(schema_type.as_ref().unwrap_or(&rust_type), &spatial_type)
+            }
+            None => {
+                error.get_or_insert(syn::Error::new(name.span(), "expected #[spatial_type(..)]"));
+                SpatialType::Type(rust_type.clone())
+            }
+        };
+        // With only `with`, the declared type is converted and not the schema type, so it
+        // can't name the types of `type` and `enum` values.
+        let type_error = match (&schema_type, &with) {
+            (Some(schema_type), _) => ty.check_rust_type(schema_type).err(),
+            (None, None) => ty.check_rust_type(&rust_type).err(),
+            (None, Some(with)) if ty.contains_user_type() => Some(syn::Error::new(
+                with.span(),
+                format!(
+                    "`with` on a {} field needs the schema-side type, add #[spatial(into = \"..\")]",
+                    ty.get_schema_name()
+                ),
+            )),
+            (None, Some(_)) => None,
+        };
+        let adapter = match (with, schema_type) {
+            (Some(path), _) => Some(Adapter::With(path)),
+            (None, Some(_)) => Some(Adapter::Into),
+            (None, None) => None,
+        };
+        let default = or_error(get_spatial_expr(&field.attrs, "default"), &mut error);
+        let since = or_error(get_spatial_option_u32(&field.attrs, "since"), &mut error);
+        let deprecated = or_error(has_spatial_flag(&field.attrs, "deprecated"), &mut error);
+        Self {
+            id,
+            name,
+            ty,
+            default,
+            rust_type,
+            adapter,
+            since,
+            deprecated,
+            error: error.or(type_error),
+        }
     }
 }
@@ -1,8 +1,20 @@
 pub mod r#enum;
 pub mod field;
+pub mod query;
 pub mod r#struct;
 pub mod r#type;
 
+pub fn get_serde_derive() -> TokenStream2 {
+    if cfg!(feature = "serde") {
+        quote! {
+            #[derive(spatialos_macro_runtime::serde::Serialize, spatialos_macro_runtime::serde::Deserialize)]
+            #[serde(crate = "spatialos_macro_runtime::serde")]
+        }
+    } else {
+        quote! {}
+    }
+}
+
 fn get_field_id(attrs: &[Attribute]) -> Option<u32> {
     let attribute = extract_attribute::<syn::Lit>(attrs, "field_id")?;
 
@@ -43,6 +55,122 @@ fn get_spatial_type(attrs: &[Attribute]) -> Option<String> {
     }
 }
 
+/// An option of `#[spatial(...)]`, either a flag or a name with a value which may be any
+/// expression, e.g. `default = 1.0 / 3.0`.
+struct SpatialOption {
+    name: Ident,
+    value: Option<Expr>,
+}
+
+impl Parse for SpatialOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { name, value })
+    }
+}
+
+fn get_spatial_options(attrs: &[Attribute]) -> syn::Result<Vec<SpatialOption>> {
+    let mut options = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("spatial")) {
+        options.extend(
+            attr.parse_args_with(Punctuated::<SpatialOption, Token![,]>::parse_terminated)?,
+        );
+    }
+    Ok(options)
+}
+
+fn has_spatial_flag(attrs: &[Attribute], name: &str) -> syn::Result<bool> {
+    Ok(get_spatial_options(attrs)?
+        .iter()
+        .any(|option| option.name == name && option.value.is_none()))
+}
+
+fn get_spatial_expr(attrs: &[Attribute], name: &str) -> syn::Result<Option<Expr>> {
+    match get_spatial_options(attrs)?
+        .into_iter()
+        .find(|option| option.name == name)
+    {
+        Some(SpatialOption {
+            value: Some(value), ..
+        }) => Ok(Some(value)),
+        Some(option) => Err(syn::Error::new(
+            option.name.span(),
+            format!("expected a value for #[spatial({} = ...)]", name),
+        )),
+        None => Ok(None),
+    }
+}
+
+fn get_spatial_option_lit_str(attrs: &[Attribute], name: &str) -> syn::Result<Option<LitStr>> {
+    match get_spatial_expr(attrs, name)? {
+        Some(Expr::Lit(ExprLit {
+            lit: Lit::Str(lit_str),
+            ..
+        })) => Ok(Some(lit_str)),
+        Some(expr) => Err(syn::Error::new(
+            expr.span(),
+            format!("expected a string for #[spatial({} = ...)]", name),
+        )),
+        None => Ok(None),
+    }
+}
+
+fn get_spatial_option_str(attrs: &[Attribute], name: &str) -> syn::Result<Option<String>> {
+    Ok(get_spatial_option_lit_str(attrs, name)?.map(|lit_str| lit_str.value()))
+}
+
+fn get_spatial_option_u32(attrs: &[Attribute], name: &str) -> syn::Result<Option<u32>> {
+    match get_spatial_expr(attrs, name)? {
+        Some(Expr::Lit(ExprLit {
+            lit: Lit::Int(lit_int),
+            ..
+        })) => lit_int.base10_parse::<u32>().map(Some),
+        Some(expr) => Err(syn::Error::new(
+            expr.span(),
+            format!("expected an integer for #[spatial({} = ...)]", name),
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Qualified schema name from `#[spatial(package = "...")]` and `#[spatial(name = "...")]`,
+/// defaulting to the name of the item.
+fn get_schema_name(attrs: &[Attribute], ident: &Ident) -> syn::Result<String> {
+    let name = get_spatial_option_str(attrs, "name")?.unwrap_or_else(|| ident.to_string());
+    Ok(match get_spatial_option_str(attrs, "package")? {
+        Some(package) => format!("{}.{}", package, name),
+        None => name,
+    })
+}
+
+/// The value of `result`, or the default value once its error is kept in `error` to be
+/// reported instead of expanding the item. Only the first error of an item is kept, since
+/// attributes which don't parse fail every option read from them.
+fn or_error<T: Default>(result: syn::Result<T>, error: &mut Option<syn::Error>) -> T {
+    result.unwrap_or_else(|result_error| {
+        error.get_or_insert(result_error);
+        T::default()
+    })
+}
+
+pub fn to_snake_case(name: &str) -> String {
+    name.chars()
+        .enumerate()
+        .fold(String::new(), |mut acc, (i, c)| {
+            if c.is_uppercase() && i > 0 {
+                acc.push('_');
+            }
+            acc.extend(c.to_lowercase());
+            acc
+        })
+}
+
 fn extract_attribute<T: Parse>(attrs: &[Attribute], name: &str) -> Option<T> {
     attrs
         .iter()
@@ -90,10 +218,219 @@ pub fn unpack_two_arg(ty: &Type) -> Option<(&Type, &Type)> {
             }
         }
         _ => None,
-    } 
+    }
 }
 
+use proc_macro2::TokenStream as TokenStream2;
 pub use r#enum::EnumAST;
 pub use r#struct::StructAST;
 pub use r#type::SpatialType;
-use syn::{Attribute, GenericArgument, PathArguments, Type, parse::Parse};
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute, Expr, ExprLit, GenericArgument, Ident, Lit, LitStr, PathArguments, Token, Type,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::{parse_quote, ItemEnum, ItemStruct};
+
+    #[test]
+    fn options_are_read() {
+        let attrs: Vec<Attribute> = vec![
+            parse_quote!(#[spatial(package = "improbable", strict)]),
+            parse_quote!(#[spatial(since = 2, default = 1.0 / 3.0)]),
+        ];
+        assert_eq!(
+            get_schema_name(&attrs, &parse_quote!(Position)).unwrap(),
+            "improbable.Position"
+        );
+        assert!(has_spatial_flag(&attrs, "strict").unwrap());
+        assert!(!has_spatial_flag(&attrs, "since").unwrap());
+        assert_eq!(get_spatial_option_u32(&attrs, "since").unwrap(), Some(2));
+        assert!(get_spatial_expr(&attrs, "default").unwrap().is_some());
+        assert_eq!(get_spatial_option_str(&attrs, "name").unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_options_are_errors() {
+        let message = |attr: Attribute, name: &str| {
+            get_spatial_option_u32(&[attr], name)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            message(parse_quote!(#[spatial(since)]), "since"),
+            "expected a value for #[spatial(since = ...)]"
+        );
+        assert_eq!(
+            message(parse_quote!(#[spatial(since = "2")]), "since"),
+            "expected an integer for #[spatial(since = ...)]"
+        );
+        assert_eq!(
+            message(parse_quote!(#[spatial(since = 4294967296)]), "since"),
+            "number too large to fit in target type"
+        );
+        assert!(get_spatial_options(&[parse_quote!(#[spatial(= 1)])]).is_err());
+        let error = get_spatial_option_str(&[parse_quote!(#[spatial(name = Position)])], "name");
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "expected a string for #[spatial(name = ...)]"
+        );
+    }
+
+    #[test]
+    fn errors_are_kept_on_the_ast() {
+        let input: ItemStruct = parse_quote! {
+            #[spatial(package = improbable)]
+            struct Position {
+                #[field_id(1)]
+                #[spatial_type("double")]
+                #[spatial(since = "1")]
+                x: f64,
+                #[spatial_type("double")]
+                y: f64,
+                #[spatial(skip, default)]
+                z: f64,
+            }
+        };
+        let ast = StructAST::from(&input);
+        assert_eq!(
+            ast.error.unwrap().to_string(),
+            "expected a string for #[spatial(package = ...)]"
+        );
+        assert_eq!(
+            ast.fields[0].error.as_ref().unwrap().to_string(),
+            "expected an integer for #[spatial(since = ...)]"
+        );
+        assert_eq!(
+            ast.fields[1].error.as_ref().unwrap().to_string(),
+            "expected #[field_id(..)]"
+        );
+        assert_eq!(
+            ast.skipped_fields[0].error.as_ref().unwrap().to_string(),
+            "expected a value for #[spatial(default = ...)]"
+        );
+    }
+
+    #[test]
+    fn adapted_types_need_their_schema_type() {
+        let input: ItemStruct = parse_quote! {
+            struct Route {
+                #[field_id(1)]
+                #[spatial_type("list<type>")]
+                #[spatial(with = "waypoints")]
+                a: Waypoints,
+                #[field_id(2)]
+                #[spatial_type("list<type>")]
+                #[spatial(with = "waypoints", into = "Vec<Coordinates>")]
+                b: Waypoints,
+                #[field_id(3)]
+                #[spatial_type("double")]
+                #[spatial(with = "meters")]
+                c: Meters,
+            }
+        };
+        let ast = StructAST::from(&input);
+        assert_eq!(
+            ast.fields[0].error.as_ref().unwrap().to_string(),
+            "`with` on a list<object> field needs the schema-side type, add #[spatial(into = \"..\")]"
+        );
+        assert!(ast.fields[1].error.is_none());
+        assert!(ast.fields[2].error.is_none());
+    }
+
+    #[test]
+    fn referenced_types_are_asserted() {
+        let assertions = |ty: Type, marker: &str| {
+            SpatialType::from_syn(&ty, marker)
+                .get_trait_assertions()
+                .to_string()
+        };
+        assert_eq!(
+            assertions(parse_quote!(Vec<crate::Coordinates>), "list<type>"),
+            quote! {
+                const _: fn() = spatialos_macro_runtime::assertions::derives_spatial_type::<crate::Coordinates>;
+            }
+            .to_string()
+        );
+        let map = assertions(parse_quote!(HashMap<u32, Color>), "map<uint32,enum>");
+        assert!(map.contains("derives_spatial_enum :: < Color >"));
+        assert!(!map.contains("derives_spatial_type"));
+        assert!(assertions(parse_quote!(Vec<f64>), "list<double>").is_empty());
+    }
+
+    #[test]
+    fn declared_types_must_match_the_schema_type() {
+        let check = |ty: Type, marker: &str| {
+            SpatialType::from_syn(&ty, marker)
+                .check_rust_type(&ty)
+                .map_err(|error| error.to_string())
+        };
+        assert_eq!(check(parse_quote!(f64), "double"), Ok(()));
+        assert_eq!(check(parse_quote!(i64), "EntityId"), Ok(()));
+        assert_eq!(check(parse_quote!(Vec<u8>), "bytes"), Ok(()));
+        assert_eq!(
+            check(
+                parse_quote!(std::collections::HashMap<String, u32>),
+                "map<string,uint32>"
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            check(parse_quote!(u32), "uint64"),
+            Err("`u32` doesn't match spatial_type \"uint64\", expected `u64`".to_owned())
+        );
+        assert_eq!(
+            check(parse_quote!(Vec<f32>), "list<double>"),
+            Err("`f32` doesn't match spatial_type \"double\", expected `f64`".to_owned())
+        );
+        assert_eq!(
+            check(parse_quote!(Vec<f64>), "option<double>"),
+            Err(
+                "`Vec<f64>` doesn't match spatial_type \"option<double>\", expected `Option<_>`"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            check(parse_quote!(String), "bytes"),
+            Err("`String` doesn't match spatial_type \"bytes\", expected `Vec<u8>`".to_owned())
+        );
+    }
+
+    #[test]
+    fn direct_self_references_are_errors() {
+        let input: ItemStruct = parse_quote! {
+            struct Node {
+                #[field_id(1)]
+                #[spatial_type("type")]
+                inner: Node,
+                #[field_id(2)]
+                #[spatial_type("option<type>")]
+                next: Option<Box<Self>>,
+            }
+        };
+        let ast = StructAST::from(&input);
+        assert_eq!(
+            ast.fields[0].error.as_ref().unwrap().to_string(),
+            "Node can't contain itself directly, use option<type> or list<type>"
+        );
+        assert!(ast.fields[1].error.is_none());
+        assert_eq!(
+            ast.fields[1].ty.get_data_type(),
+            parse_quote!(Option<<Box<Node> as spatialos_macro_runtime::schema::SchemaType>::Data>)
+        );
+    }
+
+    #[test]
+    fn enums_may_be_empty() {
+        let input: ItemEnum = parse_quote! {
+            enum Empty {}
+        };
+        let ast = EnumAST::from(&input);
+        assert!(ast.error.is_none());
+        assert!(ast.variants.is_empty());
+    }
+}
@@ -0,0 +1,401 @@
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{Ident, Type};
+
+use super::{field::FieldAST, r#struct::StructAST, SpatialType};
+
+/// Schema names of the standard library types `#[spatial(query)]` applies to, which
+/// `include_schema!` marks with it.
+pub const QUERY_SCHEMA_NAMES: &[&str] = &[
+    "improbable.Coordinates",
+    "improbable.EdgeLength",
+    "improbable.ComponentInterest.SphereConstraint",
+    "improbable.ComponentInterest.CylinderConstraint",
+    "improbable.ComponentInterest.BoxConstraint",
+    "improbable.ComponentInterest.RelativeSphereConstraint",
+    "improbable.ComponentInterest.RelativeCylinderConstraint",
+    "improbable.ComponentInterest.RelativeBoxConstraint",
+    "improbable.ComponentInterest.SelfConstraint",
+    "improbable.ComponentInterest.QueryConstraint",
+    "improbable.ComponentInterest.Query",
+];
+
+/// Implements `QuerySchema` for a `#[spatial(query)]` type of the standard library, going
+/// through the fields of the derived struct so that their ids come from the declarations. A
+/// type which isn't one of them, or lacks one of their fields, is an error.
+pub fn get_impl_query_schema(
+    ast: &StructAST,
+    data_struct_name: &Ident,
+) -> syn::Result<TokenStream2> {
+    let (value_type, to_data, from_data) = match ast.schema_name.as_str() {
+        "improbable.Coordinates" | "improbable.EdgeLength" => {
+            get_vector_conversions(ast, data_struct_name)?
+        }
+        "improbable.ComponentInterest.SphereConstraint"
+        | "improbable.ComponentInterest.CylinderConstraint" => {
+            get_round_conversions(ast, data_struct_name)?
+        }
+        "improbable.ComponentInterest.BoxConstraint" => get_box_conversions(ast, data_struct_name)?,
+        "improbable.ComponentInterest.RelativeSphereConstraint"
+        | "improbable.ComponentInterest.RelativeCylinderConstraint" => {
+            get_relative_round_conversions(ast, data_struct_name)?
+        }
+        "improbable.ComponentInterest.RelativeBoxConstraint" => {
+            get_relative_box_conversions(ast, data_struct_name)?
+        }
+        "improbable.ComponentInterest.SelfConstraint" => (
+            quote! { () },
+            quote! { #data_struct_name::default() },
+            quote! { () },
+        ),
+        "improbable.ComponentInterest.QueryConstraint" => {
+            get_constraint_conversions(ast, data_struct_name)?
+        }
+        "improbable.ComponentInterest.Query" => get_query_conversions(ast, data_struct_name)?,
+        schema_name => {
+            return Err(syn::Error::new(
+                ast.name.span(),
+                format!(
+                "query needs a type of the standard library, e.g. improbable.Coordinates, not {}",
+                schema_name
+            ),
+            ))
+        }
+    };
+    let struct_name = ast.name;
+    Ok(quote! {
+        #[automatically_derived]
+        #[allow(
+            unused_qualifications,
+            unused_variables,
+            clippy::needless_update,
+            clippy::unused_unit
+        )]
+        impl spatialos_macro_runtime::query::QuerySchema<#value_type> for #struct_name {
+            fn to_query_data(value: &#value_type) -> #data_struct_name {
+                #to_data
+            }
+
+            fn from_query_data(data: &#data_struct_name) -> #value_type {
+                #from_data
+            }
+        }
+    })
+}
+
+/// The type of the value, the code converting `value` to the data and the code converting
+/// `data` back.
+type Conversions = (TokenStream2, TokenStream2, TokenStream2);
+
+/// The field `name` of the standard library type, which must have its schema type and no
+/// adapter.
+fn get_field<'a>(
+    ast: &'a StructAST,
+    name: &str,
+    is_expected: impl Fn(&SpatialType) -> bool,
+) -> syn::Result<&'a FieldAST> {
+    ast.fields
+        .iter()
+        .find(|field| field.name == name && field.adapter.is_none() && is_expected(&field.ty))
+        .ok_or_else(|| {
+            syn::Error::new(
+                ast.name.span(),
+                format!(
+                    "query needs the field {} of {}, with its schema type and no adapter",
+                    name, ast.schema_name
+                ),
+            )
+        })
+}
+
+fn get_double(ast: &StructAST, name: &str) -> syn::Result<Ident> {
+    get_field(ast, name, |ty| matches!(ty, SpatialType::Double)).map(|field| field.name.clone())
+}
+
+/// The field of a user type, with the path of the type.
+fn get_type<'a>(
+    ast: &'a StructAST,
+    name: &str,
+    wrapper: fn(&SpatialType) -> Option<&SpatialType>,
+) -> syn::Result<(&'a Ident, &'a Type)> {
+    let is_type = |ty: &SpatialType| matches!(wrapper(ty), Some(SpatialType::Type(_)));
+    let field = get_field(ast, name, is_type)?;
+    match wrapper(&field.ty) {
+        Some(SpatialType::Type(ty)) => Ok((&field.name, ty)),
+        _ => unreachable!(),
+    }
+}
+
+fn singular(ty: &SpatialType) -> Option<&SpatialType> {
+    Some(ty)
+}
+
+fn optional(ty: &SpatialType) -> Option<&SpatialType> {
+    match ty {
+        SpatialType::Option(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+fn list(ty: &SpatialType) -> Option<&SpatialType> {
+    match ty {
+        SpatialType::List(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+fn get_vector_conversions(ast: &StructAST, data_struct_name: &Ident) -> syn::Result<Conversions> {
+    let x = get_double(ast, "x")?;
+    let y = get_double(ast, "y")?;
+    let z = get_double(ast, "z")?;
+    Ok((
+        quote! { [f64; 3] },
+        quote! {
+            #data_struct_name {
+                #x: value[0],
+                #y: value[1],
+                #z: value[2],
+                ..Default::default()
+            }
+        },
+        quote! { [data.#x, data.#y, data.#z] },
+    ))
+}
+
+fn get_round_conversions(ast: &StructAST, data_struct_name: &Ident) -> syn::Result<Conversions> {
+    let (center, center_type) = get_type(ast, "center", singular)?;
+    let radius = get_double(ast, "radius")?;
+    Ok((
+        quote! { ([f64; 3], f64) },
+        quote! {
+            #data_struct_name {
+                #center: <#center_type as spatialos_macro_runtime::query::QuerySchema<_>>::to_query_data(&value.0),
+                #radius: value.1,
+                ..Default::default()
+            }
+        },
+        quote! {
+            (
+                <#center_type as spatialos_macro_runtime::query::QuerySchema<_>>::from_query_data(&data.#center),
+                data.#radius,
+            )
+        },
+    ))
+}
+
+fn get_box_conversions(ast: &StructAST, data_struct_name: &Ident) -> syn::Result<Conversions> {
+    let (center, center_type) = get_type(ast, "center", singular)?;
+    let (edge_length, edge_length_type) = get_type(ast, "edge_length", singular)?;
+    Ok((
+        quote! { ([f64; 3], [f64; 3]) },
+        quote! {
+            #data_struct_name {
+                #center: <#center_type as spatialos_macro_runtime::query::QuerySchema<_>>::to_query_data(&value.0),
+                #edge_length: <#edge_length_type as spatialos_macro_runtime::query::QuerySchema<_>>::to_query_data(&value.1),
+                ..Default::default()
+            }
+        },
+        quote! {
+            (
+                <#center_type as spatialos_macro_runtime::query::QuerySchema<_>>::from_query_data(&data.#center),
+                <#edge_length_type as spatialos_macro_runtime::query::QuerySchema<_>>::from_query_data(&data.#edge_length),
+            )
+        },
+    ))
+}
+
+fn get_relative_round_conversions(
+    ast: &StructAST,
+    data_struct_name: &Ident,
+) -> syn::Result<Conversions> {
+    let radius = get_double(ast, "radius")?;
+    Ok((
+        quote! { f64 },
+        quote! {
+            #data_struct_name {
+                #radius: *value,
+                ..Default::default()
+            }
+        },
+        quote! { data.#radius },
+    ))
+}
+
+fn get_relative_box_conversions(
+    ast: &StructAST,
+    data_struct_name: &Ident,
+) -> syn::Result<Conversions> {
+    let (edge_length, edge_length_type) = get_type(ast, "edge_length", singular)?;
+    Ok((
+        quote! { [f64; 3] },
+        quote! {
+            #data_struct_name {
+                #edge_length: <#edge_length_type as spatialos_macro_runtime::query::QuerySchema<_>>::to_query_data(value),
+                ..Default::default()
+            }
+        },
+        quote! {
+            <#edge_length_type as spatialos_macro_runtime::query::QuerySchema<_>>::from_query_data(&data.#edge_length)
+        },
+    ))
+}
+
+fn get_constraint_conversions(
+    ast: &StructAST,
+    data_struct_name: &Ident,
+) -> syn::Result<Conversions> {
+    let (sphere, sphere_type) = get_type(ast, "sphere_constraint", optional)?;
+    let (cylinder, cylinder_type) = get_type(ast, "cylinder_constraint", optional)?;
+    let (cuboid, cuboid_type) = get_type(ast, "box_constraint", optional)?;
+    let (relative_sphere, relative_sphere_type) =
+        get_type(ast, "relative_sphere_constraint", optional)?;
+    let (relative_cylinder, relative_cylinder_type) =
+        get_type(ast, "relative_cylinder_constraint", optional)?;
+    let (relative_cuboid, relative_cuboid_type) =
+        get_type(ast, "relative_box_constraint", optional)?;
+    let entity_id = &get_field(ast, "entity_id_constraint", |ty| {
+        matches!(
+            optional(ty),
+            Some(SpatialType::Int64) | Some(SpatialType::EntityID)
+        )
+    })?
+    .name;
+    let component = &get_field(ast, "component_constraint", |ty| {
+        matches!(optional(ty), Some(SpatialType::Uint32))
+    })?
+    .name;
+    let (and, and_type) = get_type(ast, "and_constraint", list)?;
+    let (or, or_type) = get_type(ast, "or_constraint", list)?;
+    let (self_entity, self_entity_type) = get_type(ast, "self_constraint", optional)?;
+    let query_schema = quote! { spatialos_macro_runtime::query::QuerySchema<_> };
+    Ok((
+        quote! { spatialos_macro_runtime::query::Constraint },
+        quote! {
+            let mut data = #data_struct_name::default();
+            match value {
+                spatialos_macro_runtime::query::Constraint::Sphere { center, radius } => {
+                    data.#sphere = Some(<#sphere_type as #query_schema>::to_query_data(&(*center, *radius)));
+                }
+                spatialos_macro_runtime::query::Constraint::Cylinder { center, radius } => {
+                    data.#cylinder = Some(<#cylinder_type as #query_schema>::to_query_data(&(*center, *radius)));
+                }
+                spatialos_macro_runtime::query::Constraint::Box { center, edge_length } => {
+                    data.#cuboid = Some(<#cuboid_type as #query_schema>::to_query_data(&(*center, *edge_length)));
+                }
+                spatialos_macro_runtime::query::Constraint::RelativeSphere { radius } => {
+                    data.#relative_sphere = Some(<#relative_sphere_type as #query_schema>::to_query_data(radius));
+                }
+                spatialos_macro_runtime::query::Constraint::RelativeCylinder { radius } => {
+                    data.#relative_cylinder = Some(<#relative_cylinder_type as #query_schema>::to_query_data(radius));
+                }
+                spatialos_macro_runtime::query::Constraint::RelativeBox { edge_length } => {
+                    data.#relative_cuboid = Some(<#relative_cuboid_type as #query_schema>::to_query_data(edge_length));
+                }
+                spatialos_macro_runtime::query::Constraint::EntityId(entity_id) => {
+                    data.#entity_id = Some(*entity_id);
+                }
+                spatialos_macro_runtime::query::Constraint::Component(component_id) => {
+                    data.#component = Some(*component_id);
+                }
+                spatialos_macro_runtime::query::Constraint::And(constraints) => {
+                    data.#and = constraints
+                        .iter()
+                        .map(<#and_type as #query_schema>::to_query_data)
+                        .collect();
+                }
+                spatialos_macro_runtime::query::Constraint::Or(constraints) => {
+                    data.#or = constraints
+                        .iter()
+                        .map(<#or_type as #query_schema>::to_query_data)
+                        .collect();
+                }
+                spatialos_macro_runtime::query::Constraint::SelfEntity => {
+                    data.#self_entity = Some(<#self_entity_type as #query_schema>::to_query_data(&()));
+                }
+            }
+            data
+        },
+        // The first constraint set is read, an empty one reading as an `And` of nothing, since
+        // `to_data` rejects an empty `Or`.
+        quote! {
+            if let Some(sphere) = &data.#sphere {
+                let (center, radius) = <#sphere_type as #query_schema>::from_query_data(sphere);
+                spatialos_macro_runtime::query::Constraint::Sphere { center, radius }
+            } else if let Some(cylinder) = &data.#cylinder {
+                let (center, radius) = <#cylinder_type as #query_schema>::from_query_data(cylinder);
+                spatialos_macro_runtime::query::Constraint::Cylinder { center, radius }
+            } else if let Some(cuboid) = &data.#cuboid {
+                let (center, edge_length) = <#cuboid_type as #query_schema>::from_query_data(cuboid);
+                spatialos_macro_runtime::query::Constraint::Box { center, edge_length }
+            } else if let Some(relative_sphere) = &data.#relative_sphere {
+                spatialos_macro_runtime::query::Constraint::RelativeSphere {
+                    radius: <#relative_sphere_type as #query_schema>::from_query_data(relative_sphere),
+                }
+            } else if let Some(relative_cylinder) = &data.#relative_cylinder {
+                spatialos_macro_runtime::query::Constraint::RelativeCylinder {
+                    radius: <#relative_cylinder_type as #query_schema>::from_query_data(relative_cylinder),
+                }
+            } else if let Some(relative_cuboid) = &data.#relative_cuboid {
+                spatialos_macro_runtime::query::Constraint::RelativeBox {
+                    edge_length: <#relative_cuboid_type as #query_schema>::from_query_data(relative_cuboid),
+                }
+            } else if let Some(entity_id) = data.#entity_id {
+                spatialos_macro_runtime::query::Constraint::EntityId(entity_id)
+            } else if let Some(component_id) = data.#component {
+                spatialos_macro_runtime::query::Constraint::Component(component_id)
+            } else if !data.#or.is_empty() {
+                spatialos_macro_runtime::query::Constraint::Or(
+                    data.#or.iter().map(<#or_type as #query_schema>::from_query_data).collect(),
+                )
+            } else if data.#self_entity.is_some() {
+                spatialos_macro_runtime::query::Constraint::SelfEntity
+            } else {
+                spatialos_macro_runtime::query::Constraint::And(
+                    data.#and.iter().map(<#and_type as #query_schema>::from_query_data).collect(),
+                )
+            }
+        },
+    ))
+}
+
+fn get_query_conversions(ast: &StructAST, data_struct_name: &Ident) -> syn::Result<Conversions> {
+    let (constraint, constraint_type) = get_type(ast, "constraint", singular)?;
+    let full_snapshot_result = &get_field(ast, "full_snapshot_result", |ty| {
+        matches!(optional(ty), Some(SpatialType::Bool))
+    })?
+    .name;
+    let result_component_id = &get_field(ast, "result_component_id", |ty| {
+        matches!(list(ty), Some(SpatialType::Uint32))
+    })?
+    .name;
+    let frequency = &get_field(ast, "frequency", |ty| {
+        matches!(optional(ty), Some(SpatialType::Float))
+    })?
+    .name;
+    Ok((
+        quote! { spatialos_macro_runtime::query::Query },
+        quote! {
+            #data_struct_name {
+                #constraint: <#constraint_type as spatialos_macro_runtime::query::QuerySchema<_>>::to_query_data(&value.constraint),
+                #full_snapshot_result: if value.result_component_ids.is_none() {
+                    Some(true)
+                } else {
+                    None
+                },
+                #result_component_id: value.result_component_ids.clone().unwrap_or_default(),
+                #frequency: value.frequency,
+                ..Default::default()
+            }
+        },
+        quote! {
+            spatialos_macro_runtime::query::Query {
+                constraint: <#constraint_type as spatialos_macro_runtime::query::QuerySchema<_>>::from_query_data(&data.#constraint),
+                result_component_ids: if data.#full_snapshot_result == Some(true) {
+                    None
+                } else {
+                    Some(data.#result_component_id.clone())
+                },
+                frequency: data.#frequency,
+            }
+        },
+    ))
+}
@@ -2,13 +2,31 @@ use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use syn::{Fields, Ident, ItemStruct};
 
-use super::{field::FieldAST, get_id};
+use super::{
+    field::{FieldAST, SkippedFieldAST},
+    get_id, get_schema_name, get_serde_derive, has_spatial_flag, or_error,
+    query::get_impl_query_schema,
+    to_snake_case, SpatialType,
+};
 
 #[derive(Debug)]
 pub struct StructAST<'a> {
     pub id: Option<u32>,
     pub name: &'a Ident,
+    pub schema_name: String,
     pub fields: Vec<FieldAST>,
+    pub skipped_fields: Vec<SkippedFieldAST>,
+    pub test_roundtrip: bool,
+    pub strict: bool,
+    pub tracked: bool,
+    pub omit_deprecated: bool,
+    /// Whether the vtable entry points fall back to the default value on errors.
+    pub default_on_error: bool,
+    /// Whether to implement `QuerySchema` for this type of the standard library.
+    pub query: bool,
+    /// Invalid attribute of the struct, reported with the errors of its fields instead of
+    /// expanding.
+    pub error: Option<syn::Error>,
 }
 
 impl StructAST<'_> {
@@ -16,6 +34,14 @@ impl StructAST<'_> {
         self.id.is_some()
     }
 
+    /// Fields written by the serializers, leaving out deprecated ones with `omit_deprecated`.
+    fn get_serialized_fields(&self) -> impl Iterator<Item = &FieldAST> {
+        let omit_deprecated = self.omit_deprecated;
+        self.fields
+            .iter()
+            .filter(move |field| !(omit_deprecated && field.deprecated))
+    }
+
     fn get_update_constructor(&self) -> TokenStream2 {
         let idents = self
             .fields
@@ -33,11 +59,19 @@ impl StructAST<'_> {
             .iter()
             .map(|field| &field.name)
             .collect::<Vec<_>>();
+        let skipped_defaults = self.get_skipped_defaults();
         quote! {
-            Self::Data { #(#idents,)* }
+            Self::Data { #(#idents,)* #(#skipped_defaults,)* }
         }
     }
 
+    fn get_skipped_defaults(&self) -> Vec<TokenStream2> {
+        self.skipped_fields
+            .iter()
+            .map(|field| field.get_data_default())
+            .collect()
+    }
+
     fn get_data_deserializers(&self, source: &Ident) -> TokenStream2 {
         let deserializers = self
             .fields
@@ -51,8 +85,7 @@ impl StructAST<'_> {
 
     fn get_data_serializers(&self, data: &Ident, target: &Ident) -> TokenStream2 {
         let serializers = self
-            .fields
-            .iter()
+            .get_serialized_fields()
             .map(|field| field.get_data_serializer(data, target))
             .collect::<Vec<_>>();
         quote! {
@@ -73,8 +106,7 @@ impl StructAST<'_> {
 
     fn get_update_serializers(&self, data: &Ident, target: &Ident) -> TokenStream2 {
         let serializers = self
-            .fields
-            .iter()
+            .get_serialized_fields()
             .map(|field| field.get_update_serializer(data, target))
             .collect::<Vec<_>>();
         quote! {
@@ -82,23 +114,910 @@ impl StructAST<'_> {
         }
     }
 
-    fn get_impl_type(
+    fn get_impl_schema_type(
         &self,
         struct_name: &Ident,
         data_struct_name: &Ident,
         update_struct_name: &Ident,
     ) -> TokenStream2 {
-        let type_data_deserialize = {
+        let data_deserialize = {
             let source = format_ident!("source");
             let deserializers = self.get_data_deserializers(&source);
             let constructor = self.get_data_constructor();
+            quote! {
+                fn data_deserialize<O: spatialos_macro_runtime::schema::SchemaObject>(
+                    #source: &mut O,
+                ) -> Self::Data {
+                    #deserializers
+                    #constructor
+                }
+            }
+        };
+
+        let data_serialize = {
+            let target = format_ident!("target");
+            let data = format_ident!("data");
+            let serializers = self.get_data_serializers(&data, &target);
+            quote! {
+                fn data_serialize<O: spatialos_macro_runtime::schema::SchemaObject>(
+                    #data: &mut Self::Data,
+                    #target: &mut O,
+                ) {
+                    #serializers
+                }
+            }
+        };
+
+        let update_deserialize = {
+            let source = format_ident!("source");
+            let deserializers = self.get_update_deserializers(&source);
+            let constructor = self.get_update_constructor();
+            quote! {
+                fn update_deserialize<O: spatialos_macro_runtime::schema::SchemaObject>(
+                    #source: &mut O,
+                ) -> Self::Update {
+                    #deserializers
+                    #constructor
+                }
+            }
+        };
+
+        let update_serialize = {
+            let target = format_ident!("target");
+            let data = format_ident!("data");
+            let serializers = self.get_update_serializers(&data, &target);
+            quote! {
+                fn update_serialize<O: spatialos_macro_runtime::schema::SchemaObject>(
+                    #data: &mut Self::Update,
+                    #target: &mut O,
+                ) {
+                    #serializers
+                }
+            }
+        };
+
+        let validate_data = if self.strict {
+            let source = format_ident!("source");
+            let validators = self
+                .fields
+                .iter()
+                .map(|field| field.get_validator(&source))
+                .collect::<Vec<_>>();
+            let field_ids = self.fields.iter().map(|field| field.id);
+            quote! {
+                fn validate_data<O: spatialos_macro_runtime::schema::SchemaObject>(
+                    #source: &mut O,
+                ) -> Result<(), spatialos_macro_runtime::schema::SchemaError> {
+                    const FIELD_IDS: &[spatialos_macro_runtime::schema::FieldId] = &[#(#field_ids),*];
+                    let field_ids = spatialos_macro_runtime::schema::SchemaObject::unique_field_ids(#source);
+                    for field_id in field_ids.unwrap_or_default() {
+                        if !FIELD_IDS.contains(&field_id) {
+                            return Err(spatialos_macro_runtime::schema::SchemaError::new(
+                                spatialos_macro_runtime::schema::SchemaErrorKind::UnknownField(field_id),
+                            )
+                            .at(<Self as spatialos_macro_runtime::schema::SchemaType>::SCHEMA_NAME));
+                        }
+                    }
+                    #(#validators)*
+                    Ok(())
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        let validate_values = {
+            let source = format_ident!("source");
+            let validators = self
+                .fields
+                .iter()
+                .map(|field| field.get_value_validator(&source))
+                .collect::<Vec<_>>();
+            quote! {
+                #[allow(unused_variables)]
+                fn validate_values<O: spatialos_macro_runtime::schema::SchemaObject>(
+                    #source: &mut O,
+                ) -> Result<(), spatialos_macro_runtime::schema::SchemaError> {
+                    #(#validators)*
+                    Ok(())
+                }
+            }
+        };
+
+        let schema_name = &self.schema_name;
+        let compatibility = self.get_compatibility();
+        let default_on_error = if self.default_on_error {
+            quote! { const DEFAULT_ON_ERROR: bool = true; }
+        } else {
+            quote! {}
+        };
+
+        let schema_component = match self.id {
+            Some(id) => quote! {
+                #[automatically_derived]
+                #[allow(unused_qualifications)]
+                impl spatialos_macro_runtime::schema::SchemaComponent for #struct_name {
+                    const ID: u32 = #id;
+                }
+
+                #[automatically_derived]
+                #[allow(unused_qualifications)]
+                impl spatialos_macro_runtime::schema::SchemaComponentData for #data_struct_name {
+                    type Component = #struct_name;
+                }
+            },
+            None => quote! {},
+        };
+
+        quote! {
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl spatialos_macro_runtime::schema::SchemaType for #struct_name {
+                type Data = #data_struct_name;
+                type Update = #update_struct_name;
+
+                const SCHEMA_NAME: &'static str = #schema_name;
+
+                #compatibility
+
+                #default_on_error
+
+                #data_deserialize
+
+                #data_serialize
+
+                #update_deserialize
+
+                #update_serialize
+
+                #validate_data
+
+                #validate_values
+            }
+
+            #schema_component
+        }
+    }
+
+    fn get_compatibility(&self) -> TokenStream2 {
+        let added = self
+            .fields
+            .iter()
+            .filter_map(|field| {
+                let field_id = field.id;
+                let since = field.since?;
+                Some(quote! {
+                    spatialos_macro_runtime::schema::AddedField {
+                        field_id: #field_id,
+                        since: #since,
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        let removed = self
+            .fields
+            .iter()
+            .filter(|field| field.deprecated)
+            .map(|field| field.id)
+            .collect::<Vec<_>>();
+        let omits_removed = self.omit_deprecated;
+        quote! {
+            const COMPATIBILITY: spatialos_macro_runtime::schema::Compatibility =
+                spatialos_macro_runtime::schema::Compatibility {
+                    added: &[#(#added),*],
+                    removed: &[#(#removed),*],
+                    omits_removed: #omits_removed,
+                };
+        }
+    }
+
+    fn get_impl_default(
+        &self,
+        data_struct_name: &Ident,
+        update_struct_name: &Ident,
+    ) -> TokenStream2 {
+        let data_defaults = self
+            .fields
+            .iter()
+            .map(|field| field.get_data_default())
+            .chain(self.get_skipped_defaults())
+            .collect::<Vec<_>>();
+        let update_defaults = self
+            .fields
+            .iter()
+            .map(|field| field.get_update_default())
+            .collect::<Vec<_>>();
+        quote! {
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl Default for #data_struct_name {
+                fn default() -> Self {
+                    Self { #(#data_defaults,)* }
+                }
+            }
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl Default for #update_struct_name {
+                fn default() -> Self {
+                    Self { #(#update_defaults,)* }
+                }
+            }
+        }
+    }
+
+    fn get_impl_conversions(&self, struct_name: &Ident, data_struct_name: &Ident) -> TokenStream2 {
+        let value = format_ident!("value");
+        let conversions = self
+            .fields
+            .iter()
+            .map(|field| field.get_conversion(&value))
+            .chain(self.skipped_fields.iter().map(|field| {
+                let name = &field.name;
+                quote! { #name: #value.#name }
+            }))
+            .collect::<Vec<_>>();
+        quote! {
+            #[automatically_derived]
+            #[allow(unused_qualifications, unused_variables)]
+            impl From<#struct_name> for #data_struct_name {
+                fn from(#value: #struct_name) -> Self {
+                    Self { #(#conversions,)* }
+                }
+            }
+
+            #[automatically_derived]
+            #[allow(unused_qualifications, unused_variables)]
+            impl From<#data_struct_name> for #struct_name {
+                fn from(#value: #data_struct_name) -> Self {
+                    Self { #(#conversions,)* }
+                }
+            }
+        }
+    }
+
+    /// Every field of an update made from the whole `Data` is set.
+    fn get_impl_data_update(
+        &self,
+        data_struct_name: &Ident,
+        update_struct_name: &Ident,
+    ) -> TokenStream2 {
+        let data = quote! { data };
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| field.get_data_update(&data, None))
+            .collect::<Vec<_>>();
+        quote! {
+            #[automatically_derived]
+            #[allow(unused_qualifications, unused_variables)]
+            impl From<#data_struct_name> for #update_struct_name {
+                fn from(#data: #data_struct_name) -> Self {
+                    Self { #(#fields,)* }
+                }
+            }
+        }
+    }
+
+    fn get_tracked_data(
+        &self,
+        data_struct_name: &Ident,
+        update_struct_name: &Ident,
+    ) -> TokenStream2 {
+        let tracked_name = format_ident!("Tracked{}", data_struct_name);
+        let accessors = self
+            .fields
+            .iter()
+            .map(|field| {
+                let id = field.id;
+                let name = &field.name;
+                let setter = format_ident!("set_{}", name);
+                let mutable = format_ident!("{}_mut", name);
+                let ty = field.get_data_type();
+                quote! {
+                    pub fn #name(&self) -> &#ty {
+                        &self.data.#name
+                    }
+
+                    pub fn #setter(&mut self, value: #ty) {
+                        self.data.#name = value;
+                        self.dirty.insert(#id);
+                    }
+
+                    pub fn #mutable(&mut self) -> &mut #ty {
+                        self.dirty.insert(#id);
+                        &mut self.data.#name
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        let skipped_accessors = self
+            .skipped_fields
+            .iter()
+            .map(|field| {
+                let name = &field.name;
+                let setter = format_ident!("set_{}", name);
+                let mutable = format_ident!("{}_mut", name);
+                let ty = &field.ty;
+                quote! {
+                    pub fn #name(&self) -> &#ty {
+                        &self.data.#name
+                    }
+
+                    pub fn #setter(&mut self, value: #ty) {
+                        self.data.#name = value;
+                    }
+
+                    pub fn #mutable(&mut self) -> &mut #ty {
+                        &mut self.data.#name
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        let data = quote! { self.data };
+        let dirty = format_ident!("dirty");
+        let update_fields = self
+            .fields
+            .iter()
+            .map(|field| field.get_data_update(&data, Some(&dirty)))
+            .collect::<Vec<_>>();
+        quote! {
+            /// Records the ids of the fields changed through its setters.
+            #[derive(Debug, Clone, Default)]
+            pub struct #tracked_name {
+                data: #data_struct_name,
+                dirty: std::collections::BTreeSet<spatialos_macro_runtime::schema::FieldId>,
+            }
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl #tracked_name {
+                pub fn new(data: #data_struct_name) -> Self {
+                    Self {
+                        data,
+                        dirty: std::collections::BTreeSet::new(),
+                    }
+                }
+
+                pub fn data(&self) -> &#data_struct_name {
+                    &self.data
+                }
+
+                pub fn into_data(self) -> #data_struct_name {
+                    self.data
+                }
+
+                pub fn is_dirty(&self) -> bool {
+                    !self.dirty.is_empty()
+                }
+
+                pub fn dirty_fields(&self) -> impl Iterator<Item = spatialos_macro_runtime::schema::FieldId> + '_ {
+                    self.dirty.iter().copied()
+                }
+
+                #(#accessors)*
+
+                #(#skipped_accessors)*
+
+                /// Makes an update of the fields changed since the last call.
+                #[allow(unused_variables)]
+                pub fn take_update(&mut self) -> #update_struct_name {
+                    let #dirty = std::mem::take(&mut self.dirty);
+                    #update_struct_name { #(#update_fields,)* }
+                }
+            }
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl From<#data_struct_name> for #tracked_name {
+                fn from(data: #data_struct_name) -> Self {
+                    Self::new(data)
+                }
+            }
+        }
+    }
+
+    fn get_view(&self, struct_name: &Ident, data_struct_name: &Ident) -> TokenStream2 {
+        let view_name = format_ident!("{}View", struct_name);
+        let accessors = self
+            .fields
+            .iter()
+            .map(|field| field.get_view_accessor())
+            .collect::<Vec<_>>();
+        quote! {
+            /// Reads the fields of a schema object when they are accessed, nested objects and
+            /// lists being read in place instead of collected.
+            pub struct #view_name<'a, O: spatialos_macro_runtime::schema::SchemaObject> {
+                object: spatialos_macro_runtime::schema::ObjectRef<'a, O>,
+            }
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl<'a, O: spatialos_macro_runtime::schema::SchemaObject> #view_name<'a, O> {
+                pub fn new(object: &'a mut O) -> Self {
+                    Self {
+                        object: spatialos_macro_runtime::schema::ObjectRef::Borrowed(object),
+                    }
+                }
+
+                /// Deserializes every field.
+                pub fn to_data(&mut self) -> #data_struct_name {
+                    <#struct_name as spatialos_macro_runtime::schema::SchemaType>::data_deserialize(
+                        &mut *self.object,
+                    )
+                }
+
+                #(#accessors)*
+            }
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl spatialos_macro_runtime::schema::SchemaView for #struct_name {
+                type View<'a, O: spatialos_macro_runtime::schema::SchemaObject + 'a> = #view_name<'a, O>;
+
+                fn view<'a, O: spatialos_macro_runtime::schema::SchemaObject + 'a>(
+                    object: spatialos_macro_runtime::schema::ObjectRef<'a, O>,
+                ) -> Self::View<'a, O> {
+                    #view_name { object }
+                }
+            }
+        }
+    }
+
+    fn get_update_builder(&self, update_struct_name: &Ident) -> TokenStream2 {
+        let builder_name = format_ident!("{}Builder", update_struct_name);
+        let setters = self
+            .fields
+            .iter()
+            .map(|field| {
+                let name = &field.name;
+                let ty = field.ty.get_optionless_update_type();
+                let value = match field.ty {
+                    SpatialType::Type(_) | SpatialType::List(_) | SpatialType::Map(_, _) => {
+                        quote! { Some(value) }
+                    }
+                    _ => quote! { value },
+                };
+                quote! {
+                    pub fn #name(mut self, value: #ty) -> Self {
+                        self.update.#name = #value;
+                        self
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        quote! {
+            #[derive(Debug, Clone, Default)]
+            pub struct #builder_name {
+                update: #update_struct_name,
+            }
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl #update_struct_name {
+                pub fn builder() -> #builder_name {
+                    #builder_name::default()
+                }
+            }
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl #builder_name {
+                #(#setters)*
+
+                pub fn build(self) -> #update_struct_name {
+                    self.update
+                }
+            }
+        }
+    }
+
+    /// The builder has a type parameter per required field, either `Unset` or `Set`,
+    /// and can only be built once they are all `Set`.
+    fn get_data_builder(&self, data_struct_name: &Ident) -> TokenStream2 {
+        let builder_name = format_ident!("{}Builder", data_struct_name);
+        let params = self
+            .fields
+            .iter()
+            .filter(|field| field.is_required())
+            .enumerate()
+            .map(|(index, _)| format_ident!("T{}", index))
+            .collect::<Vec<_>>();
+        let unset = params
+            .iter()
+            .map(|_| quote! { spatialos_macro_runtime::builder::Unset })
+            .collect::<Vec<_>>();
+        let set = params
+            .iter()
+            .map(|_| quote! { spatialos_macro_runtime::builder::Set })
+            .collect::<Vec<_>>();
+        let mut required_index = 0;
+        let setters = self
+            .fields
+            .iter()
+            .map(|field| {
+                let name = &field.name;
+                let ty = field.get_data_type();
+                if field.is_required() {
+                    let state = params
+                        .iter()
+                        .enumerate()
+                        .map(|(index, param)| {
+                            if index == required_index {
+                                quote! { spatialos_macro_runtime::builder::Set }
+                            } else {
+                                quote! { #param }
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    required_index += 1;
+                    quote! {
+                        pub fn #name(mut self, value: #ty) -> #builder_name<#(#state),*> {
+                            self.data.#name = value;
+                            #builder_name {
+                                data: self.data,
+                                state: std::marker::PhantomData,
+                            }
+                        }
+                    }
+                } else {
+                    quote! {
+                        pub fn #name(mut self, value: #ty) -> Self {
+                            self.data.#name = value;
+                            self
+                        }
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        let skipped_setters = self
+            .skipped_fields
+            .iter()
+            .map(|field| {
+                let name = &field.name;
+                let ty = &field.ty;
+                quote! {
+                    pub fn #name(mut self, value: #ty) -> Self {
+                        self.data.#name = value;
+                        self
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        quote! {
+            #[derive(Debug, Clone)]
+            pub struct #builder_name<#(#params),*> {
+                data: #data_struct_name,
+                state: std::marker::PhantomData<(#(#params,)*)>,
+            }
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl #data_struct_name {
+                pub fn builder() -> #builder_name<#(#unset),*> {
+                    #builder_name {
+                        data: Self::default(),
+                        state: std::marker::PhantomData,
+                    }
+                }
+            }
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl<#(#params),*> #builder_name<#(#params),*> {
+                #(#setters)*
+
+                #(#skipped_setters)*
+            }
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl #builder_name<#(#set),*> {
+                pub fn build(self) -> #data_struct_name {
+                    self.data
+                }
+            }
+        }
+    }
+
+    /// Snapshot JSON conversion of the `Data` struct, keyed on field ids.
+    fn get_impl_snapshot_json(&self, data_struct_name: &Ident) -> TokenStream2 {
+        if !cfg!(feature = "json") {
+            return quote! {};
+        }
+        let data = format_ident!("data");
+        let fields = format_ident!("fields");
+        let serializers = self
+            .get_serialized_fields()
+            .map(|field| field.get_json_serializer(&data, &fields))
+            .collect::<Vec<_>>();
+        let deserializers = self
+            .fields
+            .iter()
+            .map(|field| field.get_json_deserializer(&fields))
+            .collect::<Vec<_>>();
+        let skipped_defaults = self.get_skipped_defaults();
+        let idents = self
+            .fields
+            .iter()
+            .map(|field| &field.name)
+            .collect::<Vec<_>>();
+        quote! {
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl spatialos_macro_runtime::json::SnapshotJson for #data_struct_name {
+                fn to_json(&self) -> spatialos_macro_runtime::json::Value {
+                    let #data = self;
+                    let mut #fields = spatialos_macro_runtime::json::Map::new();
+                    #(#serializers)*
+                    spatialos_macro_runtime::json::Value::Object(#fields)
+                }
+
+                fn from_json(
+                    value: &spatialos_macro_runtime::json::Value,
+                ) -> Result<Self, spatialos_macro_runtime::json::JsonError> {
+                    let #fields = spatialos_macro_runtime::json::fields(value)?;
+                    #(#deserializers)*
+                    Ok(Self { #(#idents,)* #(#skipped_defaults,)* })
+                }
+            }
+
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl #data_struct_name {
+                pub fn to_snapshot_json(&self) -> spatialos_macro_runtime::json::Value {
+                    spatialos_macro_runtime::json::SnapshotJson::to_json(self)
+                }
+
+                pub fn from_snapshot_json(
+                    value: &spatialos_macro_runtime::json::Value,
+                ) -> Result<Self, spatialos_macro_runtime::json::JsonError> {
+                    spatialos_macro_runtime::json::SnapshotJson::from_json(value)
+                }
+            }
+        }
+    }
+
+    /// Submits the component to the registry of the runtime crate, and to the snapshot reader
+    /// with the `snapshot` feature.
+    fn get_registration(&self, struct_name: &Ident) -> TokenStream2 {
+        if !cfg!(feature = "registry") || !self.is_component() {
+            return quote! {};
+        }
+        let id = self.id.unwrap();
+        let name = struct_name.to_string();
+        let schema_name = &self.schema_name;
+        let vtable = if cfg!(feature = "sdk") {
+            quote! { vtable: <#struct_name as spatialos_sdk::Component>::get_vtable, }
+        } else {
+            quote! {}
+        };
+        let snapshot = if cfg!(feature = "snapshot") {
+            quote! {
+                spatialos_macro_runtime::inventory::submit! {
+                    spatialos_macro_runtime::snapshot::SnapshotRegistration {
+                        id: #id,
+                        read: spatialos_macro_runtime::snapshot::read_component::<#struct_name>,
+                    }
+                }
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            spatialos_macro_runtime::inventory::submit! {
+                spatialos_macro_runtime::registry::ComponentRegistration {
+                    id: #id,
+                    rust_name: concat!(module_path!(), "::", #name),
+                    schema_name: #schema_name,
+                    #vtable
+                }
+            }
+
+            #snapshot
+        }
+    }
+
+    /// Describes the item for the schema bundle, and registers it with the `registry` feature.
+    fn get_bundle_entry(&self, struct_name: &Ident) -> TokenStream2 {
+        let qualified_name = &self.schema_name;
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| field.get_bundle_definition())
+            .collect::<Vec<_>>();
+        let entry = match self.id {
+            Some(component_id) => quote! {
+                spatialos_macro_runtime::bundle::BundleEntry::Component(
+                    spatialos_macro_runtime::bundle::ComponentDefinition {
+                        qualified_name: #qualified_name,
+                        component_id: #component_id,
+                        fields: vec![#(#fields,)*],
+                    },
+                )
+            },
+            None => quote! {
+                spatialos_macro_runtime::bundle::BundleEntry::Type(
+                    spatialos_macro_runtime::bundle::TypeDefinition {
+                        qualified_name: #qualified_name,
+                        fields: vec![#(#fields,)*],
+                    },
+                )
+            },
+        };
+        let registration = if cfg!(feature = "registry") {
+            quote! {
+                spatialos_macro_runtime::inventory::submit! {
+                    spatialos_macro_runtime::bundle::BundleRegistration {
+                        entry: #struct_name::schema_bundle_entry,
+                    }
+                }
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
+            impl #struct_name {
+                pub fn schema_bundle_entry() -> spatialos_macro_runtime::bundle::BundleEntry {
+                    #entry
+                }
+            }
+
+            #registration
+        }
+    }
+
+    /// Zips the strategies into nested pairs, as proptest only implements
+    /// `Strategy` for tuples of up to twelve elements.
+    fn get_struct_strategy(
+        &self,
+        constructor: TokenStream2,
+        strategies: Vec<TokenStream2>,
+    ) -> TokenStream2 {
+        let (strategy, pattern) = self.fields.iter().zip(strategies).rev().fold(
+            (quote! { Just(()) }, quote! { () }),
+            |(strategy, pattern), (field, field_strategy)| {
+                let name = &field.name;
+                (
+                    quote! { (#field_strategy, #strategy) },
+                    quote! { (#name, #pattern) },
+                )
+            },
+        );
+        quote! {
+            #strategy.prop_map(|#pattern| #constructor).boxed()
+        }
+    }
+
+    fn get_roundtrip_test(&self, struct_name: &Ident) -> TokenStream2 {
+        let left = format_ident!("left");
+        let right = format_ident!("right");
+
+        let arbitrary_data = {
+            let strategies = self
+                .fields
+                .iter()
+                .map(|field| field.get_data_strategy())
+                .collect::<Vec<_>>();
+            let strategy = self.get_struct_strategy(self.get_data_constructor(), strategies);
+            quote! {
+                fn arbitrary_data() -> spatialos_macro_runtime::proptest::strategy::BoxedStrategy<Self::Data> {
+                    use spatialos_macro_runtime::proptest::prelude::*;
+                    #strategy
+                }
+            }
+        };
+
+        let arbitrary_update = {
+            let strategies = self
+                .fields
+                .iter()
+                .map(|field| field.get_update_strategy())
+                .collect::<Vec<_>>();
+            let strategy = self.get_struct_strategy(self.get_update_constructor(), strategies);
+            quote! {
+                fn arbitrary_update() -> spatialos_macro_runtime::proptest::strategy::BoxedStrategy<Self::Update> {
+                    use spatialos_macro_runtime::proptest::prelude::*;
+                    #strategy
+                }
+            }
+        };
+
+        let data_eq = {
+            let comparisons = self
+                .get_serialized_fields()
+                .map(|field| field.get_data_eq(&left, &right))
+                .collect::<Vec<_>>();
+            quote! {
+                fn data_eq(#left: &Self::Data, #right: &Self::Data) -> bool {
+                    true #(&& #comparisons)*
+                }
+            }
+        };
+
+        let update_eq = {
+            let comparisons = self
+                .get_serialized_fields()
+                .map(|field| field.get_update_eq(&left, &right))
+                .collect::<Vec<_>>();
+            quote! {
+                fn update_eq(#left: &Self::Update, #right: &Self::Update) -> bool {
+                    true #(&& #comparisons)*
+                }
+            }
+        };
+
+        let module_name = format_ident!("{}_roundtrip", to_snake_case(&struct_name.to_string()));
+
+        quote! {
+            #[cfg(test)]
+            impl spatialos_macro_runtime::testing::ArbitrarySchema for #struct_name {
+                #arbitrary_data
+
+                #arbitrary_update
+
+                #data_eq
+
+                #update_eq
+            }
+
+            #[cfg(test)]
+            mod #module_name {
+                use super::*;
+                use spatialos_macro_runtime::proptest::prelude::*;
+                use spatialos_macro_runtime::schema::{MemoryObject, SchemaType};
+                use spatialos_macro_runtime::testing::ArbitrarySchema;
+
+                proptest! {
+                    #[test]
+                    fn data(mut data in <#struct_name as ArbitrarySchema>::arbitrary_data()) {
+                        let mut object = MemoryObject::new();
+                        <#struct_name as SchemaType>::data_serialize(&mut data, &mut object);
+                        let result = <#struct_name as SchemaType>::data_deserialize(&mut object);
+                        prop_assert!(
+                            <#struct_name as ArbitrarySchema>::data_eq(&data, &result),
+                            "{:?} was deserialized as {:?}",
+                            data,
+                            result
+                        );
+                    }
+
+                    #[test]
+                    fn update(mut update in <#struct_name as ArbitrarySchema>::arbitrary_update()) {
+                        let mut object = MemoryObject::new();
+                        <#struct_name as SchemaType>::update_serialize(&mut update, &mut object);
+                        let result = <#struct_name as SchemaType>::update_deserialize(&mut object);
+                        prop_assert!(
+                            <#struct_name as ArbitrarySchema>::update_eq(&update, &result),
+                            "{:?} was deserialized as {:?}",
+                            update,
+                            result
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "sdk")]
+    fn get_impl_type(
+        &self,
+        struct_name: &Ident,
+        data_struct_name: &Ident,
+        update_struct_name: &Ident,
+    ) -> TokenStream2 {
+        let type_data_deserialize = {
+            let source = format_ident!("source");
             quote! {
                 fn type_data_deserialize(
                     user_data: *mut core::ffi::c_void,
                     #source: &mut spatialos::schema::Object,
                 ) -> Self::Data {
-                    #deserializers
-                    #constructor
+                    spatialos_macro_runtime::schema::recover::<Self, _, _>(|| {
+                        <Self as spatialos_macro_runtime::schema::SchemaType>::try_deserialize_data(#source)
+                    })
                 }
             }
         };
@@ -106,29 +1025,31 @@ impl StructAST<'_> {
         let type_data_serialize = {
             let target = format_ident!("target");
             let data = format_ident!("data");
-            let serializers = self.get_data_serializers(&data, &target);
             quote! {
                 fn type_data_serialize(
                     user_data: *mut core::ffi::c_void,
                     #data: &mut Self::Data,
                     #target: &mut spatialos::schema::Object,
                 ) {
-                    #serializers
+                    spatialos_macro_runtime::schema::recover_serialize::<Self, _, _, _>(
+                        #data,
+                        #target,
+                        <Self as spatialos_macro_runtime::schema::SchemaType>::data_serialize,
+                    )
                 }
             }
         };
 
         let type_update_deserialize = {
             let source = format_ident!("source");
-            let deserializers = self.get_update_deserializers(&source);
-            let constructor = self.get_update_constructor();
             quote! {
                 fn type_update_deserialize(
                     user_data: *mut core::ffi::c_void,
                     #source: &mut spatialos::schema::Object,
                 ) -> Self::Update {
-                    #deserializers
-                    #constructor
+                    spatialos_macro_runtime::schema::recover::<Self, _, _>(|| {
+                        <Self as spatialos_macro_runtime::schema::SchemaType>::try_deserialize_update(#source)
+                    })
                 }
             }
         };
@@ -136,14 +1057,17 @@ impl StructAST<'_> {
         let type_update_serialize = {
             let target = format_ident!("target");
             let data = format_ident!("data");
-            let serializers = self.get_update_serializers(&data, &target);
             quote! {
                 fn type_update_serialize(
                     user_data: *mut core::ffi::c_void,
                     #data: &mut Self::Update,
                     #target: &mut spatialos::schema::Object,
                 ) {
-                    #serializers
+                    spatialos_macro_runtime::schema::recover_serialize::<Self, _, _, _>(
+                        #data,
+                        #target,
+                        <Self as spatialos_macro_runtime::schema::SchemaType>::update_serialize,
+                    )
                 }
             }
         };
@@ -172,6 +1096,8 @@ impl StructAST<'_> {
         };
 
         quote! {
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
             impl spatialos_sdk::Type for #struct_name {
                 type Data = #data_struct_name;
                 type Update = #update_struct_name;
@@ -192,6 +1118,7 @@ impl StructAST<'_> {
         }
     }
 
+    #[cfg(feature = "sdk")]
     fn get_impl_component(
         &self,
         struct_name: &Ident,
@@ -201,8 +1128,6 @@ impl StructAST<'_> {
         let id = self.id.unwrap();
         let component_data_deserialize = {
             let fields = format_ident!("fields");
-            let deserializers = self.get_data_deserializers(&fields);
-            let constructor = self.get_data_constructor();
             quote! {
                 fn component_data_deserialize(
                     component_id: spatialos::worker::ComponentId,
@@ -210,8 +1135,9 @@ impl StructAST<'_> {
                     mut source: spatialos::schema::ComponentData
                 ) -> Self::Data {
                     let mut #fields = source.get_fields();
-                    #deserializers
-                    #constructor
+                    spatialos_macro_runtime::schema::recover::<Self, _, _>(|| {
+                        <Self as spatialos_macro_runtime::schema::SchemaType>::try_deserialize_data(&mut #fields)
+                    })
                 }
             }
         };
@@ -219,7 +1145,6 @@ impl StructAST<'_> {
         let component_data_serialize = {
             let fields = format_ident!("fields");
             let data = format_ident!("data");
-            let serializers = self.get_data_serializers(&data, &fields);
             quote! {
                 fn component_data_serialize(
                     component_id: spatialos::worker::ComponentId,
@@ -228,7 +1153,11 @@ impl StructAST<'_> {
                 ) -> spatialos::schema::ComponentData {
                     let mut component_data = spatialos::schema::ComponentData::new();
                     let mut #fields = component_data.get_fields();
-                    #serializers
+                    spatialos_macro_runtime::schema::recover_serialize::<Self, _, _, _>(
+                        #data,
+                        &mut #fields,
+                        <Self as spatialos_macro_runtime::schema::SchemaType>::data_serialize,
+                    );
                     component_data
                 }
             }
@@ -236,8 +1165,6 @@ impl StructAST<'_> {
 
         let component_update_deserialize = {
             let fields = format_ident!("fields");
-            let deserializers = self.get_update_deserializers(&fields);
-            let constructor = self.get_update_constructor();
             quote! {
                 fn component_update_deserialize(
                     component_id: spatialos::worker::ComponentId,
@@ -245,8 +1172,9 @@ impl StructAST<'_> {
                     mut source: spatialos::schema::ComponentUpdate,
                 ) -> Self::Update {
                     let mut #fields = source.get_fields();
-                    #deserializers
-                    #constructor
+                    spatialos_macro_runtime::schema::recover::<Self, _, _>(|| {
+                        <Self as spatialos_macro_runtime::schema::SchemaType>::try_deserialize_update(&mut #fields)
+                    })
                 }
             }
         };
@@ -254,7 +1182,6 @@ impl StructAST<'_> {
         let component_update_serialize = {
             let fields = format_ident!("fields");
             let data = format_ident!("data");
-            let serializers = self.get_update_serializers(&data, &fields);
             quote! {
                 fn component_update_serialize(
                     component_id: spatialos::worker::ComponentId,
@@ -263,7 +1190,11 @@ impl StructAST<'_> {
                 ) -> spatialos::schema::ComponentUpdate {
                     let mut new_update = spatialos::schema::ComponentUpdate::new();
                     let mut #fields = new_update.get_fields();
-                    #serializers
+                    spatialos_macro_runtime::schema::recover_serialize::<Self, _, _, _>(
+                        #data,
+                        &mut #fields,
+                        <Self as spatialos_macro_runtime::schema::SchemaType>::update_serialize,
+                    );
                     new_update
                 }
             }
@@ -301,6 +1232,8 @@ impl StructAST<'_> {
         };
 
         quote! {
+            #[automatically_derived]
+            #[allow(unused_qualifications)]
             impl spatialos_sdk::Component for #struct_name {
 
                 const ID: u32 = #id;
@@ -327,19 +1260,41 @@ impl StructAST<'_> {
 
 impl Into<TokenStream> for StructAST<'_> {
     fn into(self) -> TokenStream {
+        let errors = self
+            .error
+            .iter()
+            .chain(self.fields.iter().filter_map(|field| field.error.as_ref()))
+            .chain(
+                self.skipped_fields
+                    .iter()
+                    .filter_map(|field| field.error.as_ref()),
+            )
+            .map(|error| error.to_compile_error())
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return quote! { #(#errors)* }.into();
+        }
+
         let struct_name = &self.name;
         let data_struct_name = format_ident!("{}Data", &self.name);
         let update_struct_name = format_ident!("{}Update", &self.name);
+        let serde_derive = get_serde_derive();
 
         let data_struct = {
             let fields = self
                 .fields
                 .iter()
                 .map(|field| field.get_data_field())
+                .chain(
+                    self.skipped_fields
+                        .iter()
+                        .map(|field| field.get_data_field()),
+                )
                 .collect::<Vec<_>>();
             quote! {
                 #[repr(C)]
                 #[derive(Debug, Clone)]
+                #serde_derive
                 pub struct #data_struct_name {
                     #(#fields,)*
                 }
@@ -354,11 +1309,35 @@ impl Into<TokenStream> for StructAST<'_> {
             quote! {
                 #[repr(C)]
                 #[derive(Debug, Clone)]
+                #serde_derive
                 pub struct #update_struct_name {
                     #(#fields,)*
                 }
             }
         };
+        let schema_implementation =
+            self.get_impl_schema_type(struct_name, &data_struct_name, &update_struct_name);
+        let default_implementation = self.get_impl_default(&data_struct_name, &update_struct_name);
+        let conversions = self.get_impl_conversions(struct_name, &data_struct_name);
+        let data_update = self.get_impl_data_update(&data_struct_name, &update_struct_name);
+        let tracked_data = if self.tracked {
+            self.get_tracked_data(&data_struct_name, &update_struct_name)
+        } else {
+            quote! {}
+        };
+        let data_builder = self.get_data_builder(&data_struct_name);
+        let update_builder = self.get_update_builder(&update_struct_name);
+        let view = self.get_view(struct_name, &data_struct_name);
+        let query_schema = if self.query {
+            match get_impl_query_schema(&self, &data_struct_name) {
+                Ok(query_schema) => query_schema,
+                Err(error) => return error.to_compile_error().into(),
+            }
+        } else {
+            quote! {}
+        };
+
+        #[cfg(feature = "sdk")]
         let implementation = {
             if self.is_component() {
                 self.get_impl_component(struct_name, &data_struct_name, &update_struct_name)
@@ -366,8 +1345,27 @@ impl Into<TokenStream> for StructAST<'_> {
                 self.get_impl_type(struct_name, &data_struct_name, &update_struct_name)
             }
         };
+        #[cfg(not(feature = "sdk"))]
+        let implementation = quote! {};
+
+        let snapshot_json = self.get_impl_snapshot_json(&data_struct_name);
+        let registration = self.get_registration(struct_name);
+        let bundle_entry = self.get_bundle_entry(struct_name);
+        let trait_assertions = self
+            .fields
+            .iter()
+            .map(|field| field.ty.get_trait_assertions())
+            .collect::<Vec<_>>();
+
+        let roundtrip_test = if self.test_roundtrip {
+            self.get_roundtrip_test(struct_name)
+        } else {
+            quote! {}
+        };
 
         let result = quote! {
+            #(#trait_assertions)*
+
             #[automatically_derived]
             #[allow(unused_qualifications)]
             #data_struct
@@ -376,9 +1374,33 @@ impl Into<TokenStream> for StructAST<'_> {
             #[allow(unused_qualifications)]
             #update_struct
 
-            #[automatically_derived]
-            #[allow(unused_qualifications)]
+            #schema_implementation
+
+            #default_implementation
+
+            #conversions
+
+            #data_update
+
+            #tracked_data
+
+            #data_builder
+
+            #update_builder
+
+            #view
+
+            #query_schema
+
             #implementation
+
+            #snapshot_json
+
+            #registration
+
+            #bundle_entry
+
+            #roundtrip_test
         };
         result.into()
     }
@@ -388,9 +1410,49 @@ impl<'a> From<&'a ItemStruct> for StructAST<'a> {
     fn from(input: &'a ItemStruct) -> Self {
         let id = get_id(&input.attrs);
         let name = &input.ident;
+        let mut error = None;
+        let schema_name = or_error(get_schema_name(&input.attrs, name), &mut error);
+        let test_roundtrip = or_error(has_spatial_flag(&input.attrs, "test_roundtrip"), &mut error);
+        let strict = or_error(has_spatial_flag(&input.attrs, "strict"), &mut error);
+        let tracked = or_error(has_spatial_flag(&input.attrs, "tracked"), &mut error);
+        let omit_deprecated = or_error(
+            has_spatial_flag(&input.attrs, "omit_deprecated"),
+            &mut error,
+        );
+        let default_on_error = or_error(
+            has_spatial_flag(&input.attrs, "default_on_error"),
+            &mut error,
+        );
+        let query = or_error(has_spatial_flag(&input.attrs, "query"), &mut error);
         if let Fields::Named(fields) = &input.fields {
-            let fields = fields.named.iter().map(FieldAST::from).collect::<Vec<_>>();
-            Self { id, name, fields }
+            // Fields whose options don't parse are not skipped, their error is reported with
+            // the field.
+            let (skipped_fields, fields): (Vec<_>, Vec<_>) = fields
+                .named
+                .iter()
+                .partition(|field| has_spatial_flag(&field.attrs, "skip").unwrap_or(false));
+            let mut fields = fields.into_iter().map(FieldAST::from).collect::<Vec<_>>();
+            for field in fields.iter_mut() {
+                field.resolve_self_references(name);
+            }
+            let skipped_fields = skipped_fields
+                .into_iter()
+                .map(SkippedFieldAST::from)
+                .collect::<Vec<_>>();
+            Self {
+                id,
+                name,
+                schema_name,
+                fields,
+                skipped_fields,
+                test_roundtrip,
+                strict,
+                tracked,
+                omit_deprecated,
+                default_on_error,
+                query,
+                error,
+            }
         } else {
             panic!("Didn't find fields for struct");
         }
@@ -1,11 +1,11 @@
 use proc_macro2::TokenStream as TokenStream2;
 use regex::Regex;
-use syn::{Field, Ident, Type};
+use syn::{spanned::Spanned, GenericArgument, Ident, PathArguments, Type};
 
 const MAP_KEY_FIELD_ID: u32 = 1u32;
 const MAP_VALUE_FIELD_ID: u32 = 2u32;
 
-use super::{get_spatial_type, unpack_one_arg, unpack_two_arg};
+use super::{unpack_one_arg, unpack_two_arg};
 
 lazy_static! {
     static ref LST_RE: Regex = Regex::new(r"list<(.*)>").unwrap();
@@ -64,21 +64,21 @@ impl SpatialType {
             _ => {
                 if LST_RE.is_match(spatial_marker) {
                     let captures = LST_RE.captures(spatial_marker).unwrap();
-                    let ty1 = unpack_one_arg(ty).expect("Can't find the one argument");
+                    let ty1 = unpack_one_arg(ty).unwrap_or(ty);
                     Self::List(Box::new(Self::from_syn(
                         ty1,
                         captures.get(1).map(|c| c.as_str()).unwrap(),
                     )))
                 } else if OPT_RE.is_match(spatial_marker) {
                     let captures = OPT_RE.captures(spatial_marker).unwrap();
-                    let ty1 = unpack_one_arg(ty).expect("Can't find the one argument");
+                    let ty1 = unpack_one_arg(ty).unwrap_or(ty);
                     Self::Option(Box::new(Self::from_syn(
                         ty1,
                         captures.get(1).map(|c| c.as_str()).unwrap(),
                     )))
                 } else if MAP_RE.is_match(spatial_marker) {
                     let captures = MAP_RE.captures(spatial_marker).unwrap();
-                    let (ty1, ty2) = unpack_two_arg(ty).expect("Can't find the one argument");
+                    let (ty1, ty2) = unpack_two_arg(ty).unwrap_or((ty, ty));
                     Self::Map(
                         Box::new(Self::from_syn(
                             ty1,
@@ -97,14 +97,6 @@ impl SpatialType {
     }
 }
 
-impl From<&Field> for SpatialType {
-    fn from(field: &Field) -> Self {
-        let ty = &field.ty;
-        let data_type = get_spatial_type(&field.attrs).expect("Can't find the spatial_type");
-        Self::from_syn(ty, data_type.as_str())
-    }
-}
-
 impl SpatialType {
     pub fn get_spatial_name(&self) -> &'static str {
         match self {
@@ -131,6 +123,26 @@ impl SpatialType {
         }
     }
 
+    /// Full schema name of the type, e.g. `map<uint32, list<object>>`.
+    pub fn get_schema_name(&self) -> String {
+        match self {
+            Self::List(spatial_type) => format!("list<{}>", spatial_type.get_schema_name()),
+            Self::Option(spatial_type) => format!("option<{}>", spatial_type.get_schema_name()),
+            Self::Map(st1, st2) => {
+                format!("map<{}, {}>", st1.get_schema_name(), st2.get_schema_name())
+            }
+            _ => self.get_spatial_name().to_owned(),
+        }
+    }
+
+    /// Name used by the schema object accessors, e.g. `get_entity_id_list`.
+    pub fn get_accessor_name(&self) -> &'static str {
+        match self {
+            Self::EntityID => "entity_id",
+            _ => self.get_spatial_name(),
+        }
+    }
+
     pub fn get_rust_type(&self) -> Type {
         match self {
             Self::Bool => syn::parse_str::<Type>("bool").unwrap(),
@@ -148,6 +160,9 @@ impl SpatialType {
             Self::Double => syn::parse_str::<Type>("f64").unwrap(),
             Self::String => syn::parse_str::<Type>("String").unwrap(),
             Self::Bytes => syn::parse_str::<Type>("Vec<u8>").unwrap(),
+            Self::EntityID => {
+                syn::parse_str::<Type>("spatialos_macro_runtime::schema::EntityId").unwrap()
+            }
             Self::Enum(ty) => ty.clone(),
             _ => panic!("Can't get rust type for {:?}", self),
         }
@@ -156,7 +171,10 @@ impl SpatialType {
     pub fn get_data_type(&self) -> Type {
         match self {
             Self::Type(ty) => match ty.clone() {
-                Type::Path(_) => syn::parse2::<Type>(quote! { <#ty as spatialos_sdk::Type>::Data }).unwrap(),
+                Type::Path(_) => syn::parse2::<Type>(
+                    quote! { <#ty as spatialos_macro_runtime::schema::SchemaType>::Data },
+                )
+                .unwrap(),
                 _ => ty.clone(),
             },
             Self::List(spatial_type) => {
@@ -170,7 +188,7 @@ impl SpatialType {
             Self::Map(spatial_type1, spatial_type2) => {
                 let ty1 = spatial_type1.get_data_type();
                 let ty2 = spatial_type2.get_data_type();
-                syn::parse2::<Type>(quote! { HashMap<#ty1, #ty2> }).unwrap()
+                syn::parse2::<Type>(quote! { std::collections::HashMap<#ty1, #ty2> }).unwrap()
             }
             _ => self.get_rust_type(),
         }
@@ -179,7 +197,10 @@ impl SpatialType {
     pub fn get_optionless_update_type(&self) -> Type {
         match self {
             Self::Type(ty) => match ty.clone() {
-                Type::Path(_) => syn::parse2::<Type>(quote! { <#ty as spatialos_sdk::Type>::Update }).unwrap(),
+                Type::Path(_) => syn::parse2::<Type>(
+                    quote! { <#ty as spatialos_macro_runtime::schema::SchemaType>::Update },
+                )
+                .unwrap(),
                 _ => ty.clone(),
             },
             Self::List(spatial_type) => {
@@ -193,7 +214,7 @@ impl SpatialType {
             Self::Map(spatial_type1, spatial_type2) => {
                 let ty1 = spatial_type1.get_optionless_update_type();
                 let ty2 = spatial_type2.get_optionless_update_type();
-                syn::parse2::<Type>(quote! { HashMap<#ty1, #ty2> }).unwrap()
+                syn::parse2::<Type>(quote! { std::collections::HashMap<#ty1, #ty2> }).unwrap()
             }
             _ => self.get_rust_type(),
         }
@@ -217,8 +238,7 @@ impl SpatialType {
     ) -> TokenStream2 {
         match self {
             Self::Type(ty) => quote! {
-                <#ty as spatialos_sdk::Type>::type_data_deserialize(
-                    user_data,
+                <#ty as spatialos_macro_runtime::schema::SchemaType>::data_deserialize(
                     &mut #object_name.index_object(#id, #index),
                 )
             },
@@ -234,8 +254,7 @@ impl SpatialType {
     ) -> TokenStream2 {
         match self {
             Self::Type(ty) => quote! {
-                <#ty as spatialos_sdk::Type>::type_update_deserialize(
-                    user_data,
+                <#ty as spatialos_macro_runtime::schema::SchemaType>::update_deserialize(
                     &mut #object_name.index_object(#id, #index),
                 )
             },
@@ -252,11 +271,20 @@ impl SpatialType {
             Self::Uint64 => quote! { #object_name.get_uint64(#id) },
             Self::Int32 => quote! { #object_name.get_int32(#id) },
             Self::Int64 => quote! { #object_name.get_int64(#id) },
+            Self::SInt32 => quote! { #object_name.get_sint32(#id) },
+            Self::SInt64 => quote! { #object_name.get_sint64(#id) },
+            Self::Fixed32 => quote! { #object_name.get_fixed32(#id) },
+            Self::Fixed64 => quote! { #object_name.get_fixed64(#id) },
+            Self::SFixed32 => quote! { #object_name.get_sfixed32(#id) },
+            Self::SFixed64 => quote! { #object_name.get_sfixed64(#id) },
             Self::String => quote! { #object_name.get_string(#id) },
             Self::Bytes => quote! { #object_name.get_bytes(#id) },
-            Self::Enum(ty) => quote! { #object_name.get_enum::<#ty>(#id) },
+            Self::EntityID => quote! { #object_name.get_entity_id(#id) },
+            Self::Enum(ty) => quote! {
+                <#ty as spatialos_macro_runtime::schema::SchemaEnum>::from_value(#object_name.get_enum::<u32>(#id))
+            },
             Self::Type(ty) => {
-                quote! { <#ty as spatialos_sdk::Type>::type_data_deserialize(user_data, &mut #object_name.get_object(#id)) }
+                quote! { <#ty as spatialos_macro_runtime::schema::SchemaType>::data_deserialize(&mut #object_name.get_object(#id)) }
             }
             Self::List(spatial_type) => match &**spatial_type {
                 SpatialType::Type(_) => {
@@ -269,15 +297,21 @@ impl SpatialType {
                         }).collect()
                     }
                 }
-                SpatialType::Enum(ty) => quote! { #object_name.get_enum_list::<#ty>(#id) },
+                SpatialType::Enum(ty) => quote! {
+                    #object_name
+                        .get_enum_list::<u32>(#id)
+                        .into_iter()
+                        .map(<#ty as spatialos_macro_runtime::schema::SchemaEnum>::from_value)
+                        .collect()
+                },
                 _ => {
-                    let name = spatial_type.get_spatial_name();
+                    let name = spatial_type.get_accessor_name();
                     let func = format_ident!("get_{}_list", name);
                     quote! { #object_name.#func(#id) }
                 }
             },
             Self::Option(spatial_type) => {
-                let func = format_ident!("get_{}_count", spatial_type.get_spatial_name());
+                let func = format_ident!("get_{}_count", spatial_type.get_accessor_name());
                 let deserializer = spatial_type.get_data_deserializer(object_name, id);
                 quote! {
                     if #object_name.#func(#id) > 0 {
@@ -318,11 +352,18 @@ impl SpatialType {
             Self::Uint64 => quote! { #target.add_uint64(#id, #data) },
             Self::Int32 => quote! { #target.add_int32(#id, #data) },
             Self::Int64 => quote! { #target.add_int64(#id, #data) },
+            Self::SInt32 => quote! { #target.add_sint32(#id, #data) },
+            Self::SInt64 => quote! { #target.add_sint64(#id, #data) },
+            Self::Fixed32 => quote! { #target.add_fixed32(#id, #data) },
+            Self::Fixed64 => quote! { #target.add_fixed64(#id, #data) },
+            Self::SFixed32 => quote! { #target.add_sfixed32(#id, #data) },
+            Self::SFixed64 => quote! { #target.add_sfixed64(#id, #data) },
             Self::String => quote! { #target.add_string(#id, &#data) },
             Self::Bytes => quote! { #target.add_bytes(#id, &#data) },
-            Self::Enum(_) => quote! { #target.add_enum(#id, &#data) },
+            Self::EntityID => quote! { #target.add_entity_id(#id, #data) },
+            Self::Enum(ty) => quote! { #target.add_enum::<#ty>(#id, &#data) },
             Self::Type(ty) => {
-                quote! { <#ty as spatialos_sdk::Type>::type_data_serialize(user_data, &mut #data, &mut #target.add_object(#id))  }
+                quote! { <#ty as spatialos_macro_runtime::schema::SchemaType>::data_serialize(&mut #data, &mut #target.add_object(#id)) }
             }
             Self::List(spatial_type) => match &**spatial_type {
                 SpatialType::Type(_) => {
@@ -336,7 +377,7 @@ impl SpatialType {
                 }
                 SpatialType::Enum(ty) => quote! { #target.add_enum_list::<#ty>(#id, &#data) },
                 _ => {
-                    let name = spatial_type.get_spatial_name();
+                    let name = spatial_type.get_accessor_name();
                     let func = format_ident!("add_{}_list", name);
                     quote! { #target.#func(#id, &#data) }
                 }
@@ -377,12 +418,20 @@ impl SpatialType {
             Self::Uint64 => quote! { #object_name.get_uint64(#id) },
             Self::Int32 => quote! { #object_name.get_int32(#id) },
             Self::Int64 => quote! { #object_name.get_int64(#id) },
+            Self::SInt32 => quote! { #object_name.get_sint32(#id) },
+            Self::SInt64 => quote! { #object_name.get_sint64(#id) },
+            Self::Fixed32 => quote! { #object_name.get_fixed32(#id) },
+            Self::Fixed64 => quote! { #object_name.get_fixed64(#id) },
+            Self::SFixed32 => quote! { #object_name.get_sfixed32(#id) },
+            Self::SFixed64 => quote! { #object_name.get_sfixed64(#id) },
             Self::String => quote! { #object_name.get_string(#id) },
             Self::Bytes => quote! { #object_name.get_bytes(#id) },
-            Self::Enum(ty) => quote! { #object_name.get_enum::<#ty>(#id) },
+            Self::EntityID => quote! { #object_name.get_entity_id(#id) },
+            Self::Enum(ty) => quote! {
+                <#ty as spatialos_macro_runtime::schema::SchemaEnum>::from_value(#object_name.get_enum::<u32>(#id))
+            },
             Self::Type(ty) => quote! {
-                <#ty as spatialos_sdk::Type>::type_update_deserialize(
-                    user_data,
+                <#ty as spatialos_macro_runtime::schema::SchemaType>::update_deserialize(
                     &mut #object_name.get_object(#id),
                 )
             },
@@ -397,15 +446,21 @@ impl SpatialType {
                         }).collect()
                     }
                 }
-                SpatialType::Enum(ty) => quote! { #object_name.get_enum_list::<#ty>(#id) },
+                SpatialType::Enum(ty) => quote! {
+                    #object_name
+                        .get_enum_list::<u32>(#id)
+                        .into_iter()
+                        .map(<#ty as spatialos_macro_runtime::schema::SchemaEnum>::from_value)
+                        .collect()
+                },
                 _ => {
-                    let name = spatial_type.get_spatial_name();
+                    let name = spatial_type.get_accessor_name();
                     let func = format_ident!("get_optional_{}_list", name);
                     quote! { #object_name.#func(#id) }
                 }
             },
             Self::Option(spatial_type) => {
-                let func = format_ident!("get_{}_count", spatial_type.get_spatial_name());
+                let func = format_ident!("get_{}_count", spatial_type.get_accessor_name());
                 let deserializer = spatial_type.get_optionless_update_deserializer(object_name, id);
                 quote! {
                     if #object_name.#func(#id) > 0 {
@@ -417,8 +472,10 @@ impl SpatialType {
             }
             Self::Map(st1, st2) => {
                 let object_ident = format_ident!("object");
-                let deserializer1 = st1.get_optionless_update_deserializer(&object_ident, MAP_KEY_FIELD_ID);
-                let deserializer2 = st2.get_optionless_update_deserializer(&object_ident, MAP_VALUE_FIELD_ID);
+                let deserializer1 =
+                    st1.get_optionless_update_deserializer(&object_ident, MAP_KEY_FIELD_ID);
+                let deserializer2 =
+                    st2.get_optionless_update_deserializer(&object_ident, MAP_VALUE_FIELD_ID);
                 quote! {
                     (0..#object_name.get_object_count(#id)).map(|i| {
                         let mut #object_ident = #object_name.index_object(#id, i);
@@ -445,9 +502,19 @@ impl SpatialType {
                         }
                     }
                 }
+                Self::Enum(_) => {
+                    let deserializer = self.get_optionless_update_deserializer(object_name, id);
+                    quote! {
+                        if #object_name.get_enum_count(#id) > 0 {
+                            Some(#deserializer)
+                        } else {
+                            None
+                        }
+                    }
+                }
                 _ => self.get_optionless_update_deserializer(object_name, id),
             },
-            Self::Type(_) | Self::Map(_, _)  => {
+            Self::Type(_) | Self::Map(_, _) => {
                 let deserializer = self.get_optionless_update_deserializer(object_name, id);
                 quote! {
                     if #object_name.get_object_count(#id) > 0 {
@@ -475,11 +542,18 @@ impl SpatialType {
             Self::Uint64 => quote! { #target.add_uint64(#id, #data) },
             Self::Int32 => quote! { #target.add_int32(#id, #data) },
             Self::Int64 => quote! { #target.add_int64(#id, #data) },
+            Self::SInt32 => quote! { #target.add_sint32(#id, #data) },
+            Self::SInt64 => quote! { #target.add_sint64(#id, #data) },
+            Self::Fixed32 => quote! { #target.add_fixed32(#id, #data) },
+            Self::Fixed64 => quote! { #target.add_fixed64(#id, #data) },
+            Self::SFixed32 => quote! { #target.add_sfixed32(#id, #data) },
+            Self::SFixed64 => quote! { #target.add_sfixed64(#id, #data) },
             Self::String => quote! { #target.add_string(#id, &#data) },
             Self::Bytes => quote! { #target.add_bytes(#id, &#data) },
-            Self::Enum(_) => quote! { #target.add_enum(#id, &#data) },
+            Self::EntityID => quote! { #target.add_entity_id(#id, #data) },
+            Self::Enum(ty) => quote! { #target.add_enum::<#ty>(#id, &#data) },
             Self::Type(ty) => {
-                quote! { <#ty as spatialos_sdk::Type>::type_update_serialize(user_data, &mut #data, &mut #target.add_object(#id)) }
+                quote! { <#ty as spatialos_macro_runtime::schema::SchemaType>::update_serialize(&mut #data, &mut #target.add_object(#id)) }
             }
             Self::List(spatial_type) => match &**spatial_type {
                 SpatialType::Type(_) => {
@@ -496,7 +570,7 @@ impl SpatialType {
                     quote! { #target.add_enum_list::<#ty>(#id, &#data) }
                 }
                 _ => {
-                    let name = spatial_type.get_spatial_name();
+                    let name = spatial_type.get_accessor_name();
                     let func = format_ident!("add_{}_list", name);
                     quote! { #target.#func(#id, #data) }
                 }
@@ -551,3 +625,663 @@ impl SpatialType {
         }
     }
 }
+
+impl SpatialType {
+    /// Whether the field holds a single value, which is absent when its count is zero.
+    pub fn is_singular(&self) -> bool {
+        !matches!(self, Self::List(_) | Self::Option(_) | Self::Map(_, _))
+    }
+
+    /// Replaces `Self` with the name of the struct and boxes the types referring to it, as the
+    /// `Data` and `Update` structs would otherwise be infinitely sized. Lists and maps already
+    /// store their values indirectly.
+    pub fn resolve_self_references(&mut self, name: &Ident, indirect: bool) {
+        match self {
+            Self::Type(ty) if is_self_reference(ty, name) => {
+                *ty = if indirect && !is_boxed(ty) {
+                    syn::parse2(quote! { #name }).unwrap()
+                } else {
+                    syn::parse2(quote! { Box<#name> }).unwrap()
+                };
+            }
+            Self::Option(spatial_type) => spatial_type.resolve_self_references(name, false),
+            Self::List(spatial_type) => spatial_type.resolve_self_references(name, true),
+            Self::Map(spatial_type1, spatial_type2) => {
+                spatial_type1.resolve_self_references(name, true);
+                spatial_type2.resolve_self_references(name, true);
+            }
+            _ => {}
+        }
+    }
+
+    /// A singular field of the type itself, which no finite value has.
+    pub fn is_direct_self_reference(&self, name: &Ident) -> bool {
+        matches!(self, Self::Type(ty) if is_self_reference(ty, name))
+    }
+
+    /// Compares the declared type of a field with the Rust type of its schema type, the error
+    /// pointing at the first part of `ty` which differs.
+    pub fn check_rust_type(&self, ty: &Type) -> syn::Result<()> {
+        match self {
+            Self::Type(_) | Self::Enum(_) | Self::Entity => Ok(()),
+            Self::List(spatial_type) => {
+                let args = self.check_container(ty, "Vec", "Vec<_>", 1)?;
+                spatial_type.check_rust_type(args[0])
+            }
+            Self::Option(spatial_type) => {
+                let args = self.check_container(ty, "Option", "Option<_>", 1)?;
+                spatial_type.check_rust_type(args[0])
+            }
+            Self::Map(spatial_type1, spatial_type2) => {
+                let args = self.check_container(ty, "HashMap", "HashMap<_, _>", 2)?;
+                spatial_type1.check_rust_type(args[0])?;
+                spatial_type2.check_rust_type(args[1])
+            }
+            Self::Bytes => {
+                let args = self.check_container(ty, "Vec", "Vec<u8>", 1)?;
+                match get_path_segment(args[0]) {
+                    Some((ident, inner)) if ident == "u8" && inner.is_empty() => Ok(()),
+                    _ => Err(self.mismatch(ty, "Vec<u8>")),
+                }
+            }
+            _ => {
+                let expected = self.get_rust_type();
+                let expected_name = get_path_segment(&expected).map(|(ident, _)| ident.to_string());
+                let found = get_path_segment(ty);
+                let matches = match (&expected_name, found) {
+                    (Some(expected), Some((ident, args))) if args.is_empty() => {
+                        ident == expected || matches!(self, Self::EntityID) && ident == "i64"
+                    }
+                    _ => false,
+                };
+                if matches {
+                    Ok(())
+                } else {
+                    Err(self.mismatch(ty, &expected_name.unwrap_or_default()))
+                }
+            }
+        }
+    }
+
+    fn check_container<'a>(
+        &self,
+        ty: &'a Type,
+        name: &str,
+        expected: &str,
+        arg_count: usize,
+    ) -> syn::Result<Vec<&'a Type>> {
+        match get_path_segment(ty) {
+            Some((ident, args)) if ident == name && args.len() == arg_count => Ok(args),
+            _ => Err(self.mismatch(ty, expected)),
+        }
+    }
+
+    fn mismatch(&self, ty: &Type, expected: &str) -> syn::Error {
+        syn::Error::new(
+            ty.span(),
+            format!(
+                "`{}` doesn't match spatial_type \"{}\", expected `{}`",
+                quote! { #ty }.to_string().replace(' ', ""),
+                self.get_schema_name(),
+                expected
+            ),
+        )
+    }
+
+    /// Checks that the types of `type` and `enum` fields derive the matching trait, the
+    /// errors pointing at the type of the field.
+    pub fn get_trait_assertions(&self) -> TokenStream2 {
+        match self {
+            Self::Type(ty) => quote_spanned! {ty.span()=>
+                const _: fn() = spatialos_macro_runtime::assertions::derives_spatial_type::<#ty>;
+            },
+            Self::Enum(ty) => quote_spanned! {ty.span()=>
+                const _: fn() = spatialos_macro_runtime::assertions::derives_spatial_enum::<#ty>;
+            },
+            Self::List(spatial_type) | Self::Option(spatial_type) => {
+                spatial_type.get_trait_assertions()
+            }
+            Self::Map(spatial_type1, spatial_type2) => {
+                let key = spatial_type1.get_trait_assertions();
+                let value = spatial_type2.get_trait_assertions();
+                quote! { #key #value }
+            }
+            _ => quote! {},
+        }
+    }
+
+    /// Description of the field type in the schema bundle.
+    pub fn get_bundle_type(&self) -> TokenStream2 {
+        match self {
+            Self::List(spatial_type) => {
+                let value_type = spatial_type.get_bundle_value_type();
+                quote! { spatialos_macro_runtime::bundle::FieldType::List(#value_type) }
+            }
+            Self::Option(spatial_type) => {
+                let value_type = spatial_type.get_bundle_value_type();
+                quote! { spatialos_macro_runtime::bundle::FieldType::Option(#value_type) }
+            }
+            Self::Map(spatial_type1, spatial_type2) => {
+                let key_type = spatial_type1.get_bundle_value_type();
+                let value_type = spatial_type2.get_bundle_value_type();
+                quote! { spatialos_macro_runtime::bundle::FieldType::Map(#key_type, #value_type) }
+            }
+            _ => {
+                let value_type = self.get_bundle_value_type();
+                quote! { spatialos_macro_runtime::bundle::FieldType::Singular(#value_type) }
+            }
+        }
+    }
+
+    fn get_bundle_value_type(&self) -> TokenStream2 {
+        match self {
+            Self::Type(ty) => quote! {
+                spatialos_macro_runtime::bundle::ValueType::Type(
+                    <#ty as spatialos_macro_runtime::schema::SchemaType>::SCHEMA_NAME,
+                )
+            },
+            Self::Enum(ty) => quote! {
+                spatialos_macro_runtime::bundle::ValueType::Enum(
+                    <#ty as spatialos_macro_runtime::schema::SchemaEnum>::SCHEMA_NAME,
+                )
+            },
+            _ => {
+                let name = self.get_spatial_name();
+                quote! { spatialos_macro_runtime::bundle::ValueType::Primitive(#name) }
+            }
+        }
+    }
+
+    /// Type returned by the accessor of the field in the view, lists and maps being iterated
+    /// without collecting them.
+    pub fn get_view_type(&self) -> TokenStream2 {
+        match self {
+            Self::Type(ty) => quote! {
+                <#ty as spatialos_macro_runtime::schema::SchemaView>::View<'_, O>
+            },
+            Self::Option(spatial_type) => {
+                let ty = spatial_type.get_view_type();
+                quote! { Option<#ty> }
+            }
+            Self::List(spatial_type) => {
+                let ty = spatial_type.get_view_type();
+                quote! { impl ExactSizeIterator<Item = #ty> + '_ }
+            }
+            Self::Map(st1, st2) => {
+                let ty1 = st1.get_entry_view_type();
+                let ty2 = st2.get_entry_view_type();
+                quote! { impl ExactSizeIterator<Item = (#ty1, #ty2)> + '_ }
+            }
+            Self::String => quote! { std::borrow::Cow<'_, str> },
+            Self::Bytes => quote! { std::borrow::Cow<'_, [u8]> },
+            _ => {
+                let ty = self.get_data_type();
+                quote! { #ty }
+            }
+        }
+    }
+
+    /// Strings and bytes of map entries are copied, as the entry objects only live while
+    /// they are read.
+    fn get_entry_view_type(&self) -> TokenStream2 {
+        match self {
+            Self::String | Self::Bytes => {
+                let ty = self.get_data_type();
+                quote! { #ty }
+            }
+            _ => self.get_view_type(),
+        }
+    }
+
+    fn get_entry_view_reader(&self, object_name: &Ident, id: u32) -> TokenStream2 {
+        match self {
+            Self::String => quote! { #object_name.get_str(#id).into_owned() },
+            Self::Bytes => self.get_data_deserializer(object_name, id),
+            _ => self.get_view_reader(object_name, id),
+        }
+    }
+
+    /// Nested objects are read through `&mut`, as `get_object` and `index_object` are.
+    pub fn is_view_mutable(&self) -> bool {
+        matches!(self, Self::Map(_, _)) || self.contains_type()
+    }
+
+    /// Reads the field for the view, strings and bytes being borrowed when the object can
+    /// lend them out.
+    pub fn get_view_reader(&self, object_name: &Ident, id: u32) -> TokenStream2 {
+        match self {
+            Self::Type(ty) => quote! {
+                <#ty as spatialos_macro_runtime::schema::SchemaView>::view(
+                    spatialos_macro_runtime::schema::ObjectRef::Owned(#object_name.get_object(#id)),
+                )
+            },
+            Self::Option(spatial_type) => {
+                let func = format_ident!("get_{}_count", spatial_type.get_accessor_name());
+                let reader = spatial_type.get_view_reader(object_name, id);
+                quote! {
+                    if #object_name.#func(#id) > 0 {
+                        Some(#reader)
+                    } else {
+                        None
+                    }
+                }
+            }
+            Self::List(spatial_type) => {
+                let count = format_ident!("get_{}_count", spatial_type.get_accessor_name());
+                let index = format_ident!("index");
+                let reader = spatial_type.get_indexed_view_reader(object_name, id, &index);
+                quote! {
+                    (0..#object_name.#count(#id)).map(move |#index| #reader)
+                }
+            }
+            Self::Map(st1, st2) => {
+                let entry = format_ident!("entry");
+                let binding = if self.contains_type() {
+                    quote! { mut #entry }
+                } else {
+                    quote! { #entry }
+                };
+                let reader1 = st1.get_entry_view_reader(&entry, MAP_KEY_FIELD_ID);
+                let reader2 = st2.get_entry_view_reader(&entry, MAP_VALUE_FIELD_ID);
+                quote! {
+                    (0..#object_name.get_object_count(#id)).map(move |index| {
+                        let #binding = #object_name.index_object(#id, index);
+                        (#reader1, #reader2)
+                    })
+                }
+            }
+            Self::String => quote! { #object_name.get_str(#id) },
+            Self::Bytes => quote! { #object_name.get_bytes_ref(#id) },
+            _ => self.get_data_deserializer(object_name, id),
+        }
+    }
+
+    fn get_indexed_view_reader(&self, object_name: &Ident, id: u32, index: &Ident) -> TokenStream2 {
+        match self {
+            Self::Type(ty) => quote! {
+                <#ty as spatialos_macro_runtime::schema::SchemaView>::view(
+                    spatialos_macro_runtime::schema::ObjectRef::Owned(
+                        #object_name.index_object(#id, #index),
+                    ),
+                )
+            },
+            Self::Enum(ty) => quote! {
+                <#ty as spatialos_macro_runtime::schema::SchemaEnum>::from_value(
+                    #object_name.index_enum::<u32>(#id, #index),
+                )
+            },
+            Self::String => quote! { #object_name.index_str(#id, #index) },
+            Self::Bytes => quote! { #object_name.index_bytes_ref(#id, #index) },
+            _ => {
+                let func = format_ident!("index_{}", self.get_accessor_name());
+                quote! { #object_name.#func(#id, #index) }
+            }
+        }
+    }
+
+    /// Checks the values of the nested objects of `#[spatial(strict)]` types.
+    pub fn get_nested_validator(&self, object_name: &Ident, id: u32) -> TokenStream2 {
+        match self {
+            Self::Type(ty) => quote! {
+                for index in 0..#object_name.get_object_count(#id) {
+                    <#ty as spatialos_macro_runtime::schema::SchemaType>::validate_data(
+                        &mut #object_name.index_object(#id, index),
+                    )?;
+                }
+            },
+            Self::List(spatial_type) | Self::Option(spatial_type) => {
+                spatial_type.get_nested_validator(object_name, id)
+            }
+            _ => quote! {},
+        }
+    }
+
+    /// Checks every value of the field, for the fallible deserializers.
+    pub fn get_value_validator(&self, object_name: &Ident, id: u32, field: &str) -> TokenStream2 {
+        match self {
+            Self::Enum(ty) => quote! {
+                for index in 0..#object_name.get_enum_count(#id) {
+                    let value = #object_name.index_enum::<u32>(#id, index);
+                    if let Err(error) = <#ty as std::convert::TryFrom<u32>>::try_from(value) {
+                        return Err(error.at(#field));
+                    }
+                }
+            },
+            Self::String => quote! {
+                for index in 0..#object_name.get_bytes_count(#id) {
+                    if let Err(error) = std::str::from_utf8(&#object_name.index_bytes(#id, index)) {
+                        return Err(spatialos_macro_runtime::schema::SchemaError::new(
+                            spatialos_macro_runtime::schema::SchemaErrorKind::InvalidString(error),
+                        )
+                        .at(#field));
+                    }
+                }
+            },
+            Self::Type(ty) => quote! {
+                for index in 0..#object_name.get_object_count(#id) {
+                    <#ty as spatialos_macro_runtime::schema::SchemaType>::validate_values(
+                        &mut #object_name.index_object(#id, index),
+                    )
+                    .map_err(|error| error.at(#field))?;
+                }
+            },
+            Self::List(spatial_type) | Self::Option(spatial_type) => {
+                spatial_type.get_value_validator(object_name, id, field)
+            }
+            Self::Map(st1, st2) => {
+                let entry = format_ident!("entry");
+                let validator1 = st1.get_value_validator(&entry, MAP_KEY_FIELD_ID, field);
+                let validator2 = st2.get_value_validator(&entry, MAP_VALUE_FIELD_ID, field);
+                quote! {
+                    for index in 0..#object_name.get_object_count(#id) {
+                        let mut #entry = #object_name.index_object(#id, index);
+                        #validator1
+                        #validator2
+                    }
+                }
+            }
+            _ => quote! {},
+        }
+    }
+
+    /// Converts between the annotated struct and its `Data` struct, which only differ by
+    /// their nested types.
+    pub fn get_conversion(&self, value: &TokenStream2) -> TokenStream2 {
+        if !self.contains_type() {
+            return value.clone();
+        }
+        let inner = format_ident!("value");
+        match self {
+            Self::Type(ty) if is_boxed(ty) => quote! { Box::new((*#value).into()) },
+            Self::Type(_) => quote! { #value.into() },
+            Self::List(spatial_type) => {
+                let conversion = spatial_type.get_conversion(&quote! { #inner });
+                quote! { #value.into_iter().map(|#inner| #conversion).collect() }
+            }
+            Self::Option(spatial_type) => {
+                let conversion = spatial_type.get_conversion(&quote! { #inner });
+                quote! { #value.map(|#inner| #conversion) }
+            }
+            Self::Map(st1, st2) => {
+                let key = format_ident!("key");
+                let key_conversion = st1.get_conversion(&quote! { #key });
+                let value_conversion = st2.get_conversion(&quote! { #inner });
+                quote! {
+                    #value
+                        .into_iter()
+                        .map(|(#key, #inner)| (#key_conversion, #value_conversion))
+                        .collect()
+                }
+            }
+            _ => value.clone(),
+        }
+    }
+
+    /// Whether the `Update` struct wraps the field in an `Option`, `None` leaving it unchanged.
+    pub fn is_update_optional(&self) -> bool {
+        matches!(self, Self::Type(_) | Self::List(_) | Self::Map(_, _))
+    }
+
+    /// Enums default to their first value, everything else to `Default::default()`.
+    pub fn get_data_default(&self) -> TokenStream2 {
+        match self {
+            Self::Enum(ty) => quote! {
+                <#ty as spatialos_macro_runtime::schema::SchemaEnum>::first()
+            },
+            _ => quote! { Default::default() },
+        }
+    }
+
+    pub fn get_update_default(&self) -> TokenStream2 {
+        if self.is_update_optional() {
+            quote! { None }
+        } else {
+            self.get_data_default()
+        }
+    }
+
+    pub fn contains_bytes(&self) -> bool {
+        match self {
+            Self::Bytes => true,
+            Self::List(spatial_type) | Self::Option(spatial_type) => spatial_type.contains_bytes(),
+            Self::Map(st1, st2) => st1.contains_bytes() || st2.contains_bytes(),
+            _ => false,
+        }
+    }
+
+    pub fn contains_type(&self) -> bool {
+        match self {
+            Self::Type(_) => true,
+            Self::List(spatial_type) | Self::Option(spatial_type) => spatial_type.contains_type(),
+            Self::Map(st1, st2) => st1.contains_type() || st2.contains_type(),
+            _ => false,
+        }
+    }
+
+    /// Types and enums are named by the Rust type of the field, unlike primitives.
+    pub fn contains_user_type(&self) -> bool {
+        match self {
+            Self::Type(_) | Self::Enum(_) => true,
+            Self::List(spatial_type) | Self::Option(spatial_type) => {
+                spatial_type.contains_user_type()
+            }
+            Self::Map(st1, st2) => st1.contains_user_type() || st2.contains_user_type(),
+            _ => false,
+        }
+    }
+
+    /// Defers the strategies of containers of types, which may be recursive.
+    fn get_nested_strategy(&self, strategy: TokenStream2) -> TokenStream2 {
+        if self.contains_type() {
+            quote! {
+                spatialos_macro_runtime::testing::nested(|| {
+                    use spatialos_macro_runtime::proptest::prelude::*;
+                    #strategy.boxed()
+                })
+            }
+        } else {
+            strategy
+        }
+    }
+
+    pub fn get_data_strategy(&self) -> TokenStream2 {
+        match self {
+            Self::Bytes => quote! { prop::collection::vec(any::<u8>(), 0..8) },
+            Self::Enum(ty) => quote! {
+                prop::sample::select(<#ty as spatialos_macro_runtime::schema::SchemaEnum>::VALUES)
+                    .prop_map(<#ty as spatialos_macro_runtime::schema::SchemaEnum>::from_value)
+            },
+            Self::Type(ty) => quote! {
+                <#ty as spatialos_macro_runtime::testing::ArbitrarySchema>::arbitrary_data()
+            },
+            Self::List(spatial_type) => {
+                let strategy = spatial_type.get_data_strategy();
+                self.get_nested_strategy(quote! { prop::collection::vec(#strategy, 0..4) })
+            }
+            Self::Option(spatial_type) => {
+                let strategy = spatial_type.get_data_strategy();
+                self.get_nested_strategy(quote! { prop::option::of(#strategy) })
+            }
+            Self::Map(st1, st2) => {
+                let strategy1 = st1.get_data_strategy();
+                let strategy2 = st2.get_data_strategy();
+                self.get_nested_strategy(quote! {
+                    prop::collection::hash_map(#strategy1, #strategy2, 0..4)
+                })
+            }
+            Self::Entity => panic!("Can't get data_strategy for {:?}", self),
+            _ => {
+                let ty = self.get_rust_type();
+                quote! { any::<#ty>() }
+            }
+        }
+    }
+
+    pub fn get_optionless_update_strategy(&self) -> TokenStream2 {
+        match self {
+            Self::Type(ty) => quote! {
+                <#ty as spatialos_macro_runtime::testing::ArbitrarySchema>::arbitrary_update()
+            },
+            // An empty list or map is serialized as nothing and read back as `None`
+            Self::List(spatial_type) => {
+                let strategy = spatial_type.get_optionless_update_strategy();
+                quote! { prop::collection::vec(#strategy, 1..4) }
+            }
+            Self::Option(spatial_type) => {
+                let strategy = spatial_type.get_optionless_update_strategy();
+                quote! { prop::option::of(#strategy) }
+            }
+            Self::Map(st1, st2) => {
+                let strategy1 = st1.get_optionless_update_strategy();
+                let strategy2 = st2.get_optionless_update_strategy();
+                quote! { prop::collection::hash_map(#strategy1, #strategy2, 1..4) }
+            }
+            _ => self.get_data_strategy(),
+        }
+    }
+
+    pub fn get_update_strategy(&self) -> TokenStream2 {
+        match self {
+            Self::List(_) | Self::Map(_, _) => {
+                let strategy = self.get_optionless_update_strategy();
+                self.get_nested_strategy(quote! { prop::option::of(#strategy) })
+            }
+            Self::Type(_) => {
+                let strategy = self.get_optionless_update_strategy();
+                quote! { prop::option::of(#strategy) }
+            }
+            Self::Option(_) => self.get_nested_strategy(self.get_optionless_update_strategy()),
+            _ => self.get_optionless_update_strategy(),
+        }
+    }
+
+    fn get_option_eq(
+        inner: TokenStream2,
+        left: &TokenStream2,
+        right: &TokenStream2,
+    ) -> TokenStream2 {
+        quote! {
+            match (#left, #right) {
+                (Some(left), Some(right)) => #inner,
+                (None, None) => true,
+                _ => false,
+            }
+        }
+    }
+
+    fn get_collection_eq(
+        &self,
+        inner: TokenStream2,
+        left: &TokenStream2,
+        right: &TokenStream2,
+    ) -> TokenStream2 {
+        match self {
+            Self::List(_) => quote! {
+                #left.len() == #right.len()
+                    && #left.iter().zip(#right.iter()).all(|(left, right)| #inner)
+            },
+            Self::Map(_, _) => quote! {
+                #left.len() == #right.len()
+                    && #left.iter().all(|(key, left)| {
+                        #right.get(key).map_or(false, |right| #inner)
+                    })
+            },
+            _ => panic!("{:?} is not a collection", self),
+        }
+    }
+
+    pub fn get_data_eq(&self, left: &TokenStream2, right: &TokenStream2) -> TokenStream2 {
+        let inner_left = quote! { left };
+        let inner_right = quote! { right };
+        match self {
+            Self::Float | Self::Double => quote! { #left.to_bits() == #right.to_bits() },
+            Self::Enum(_) => quote! {
+                Into::<u32>::into(#left) == Into::<u32>::into(#right)
+            },
+            Self::Type(ty) => quote! {
+                <#ty as spatialos_macro_runtime::testing::ArbitrarySchema>::data_eq(#left, #right)
+            },
+            Self::List(spatial_type) | Self::Map(_, spatial_type) => {
+                let inner = spatial_type.get_data_eq(&inner_left, &inner_right);
+                self.get_collection_eq(inner, left, right)
+            }
+            Self::Option(spatial_type) => {
+                let inner = spatial_type.get_data_eq(&inner_left, &inner_right);
+                Self::get_option_eq(inner, left, right)
+            }
+            _ => quote! { #left == #right },
+        }
+    }
+
+    pub fn get_optionless_update_eq(
+        &self,
+        left: &TokenStream2,
+        right: &TokenStream2,
+    ) -> TokenStream2 {
+        let inner_left = quote! { left };
+        let inner_right = quote! { right };
+        match self {
+            Self::Type(ty) => quote! {
+                <#ty as spatialos_macro_runtime::testing::ArbitrarySchema>::update_eq(#left, #right)
+            },
+            Self::List(spatial_type) | Self::Map(_, spatial_type) => {
+                let inner = spatial_type.get_optionless_update_eq(&inner_left, &inner_right);
+                self.get_collection_eq(inner, left, right)
+            }
+            Self::Option(spatial_type) => {
+                let inner = spatial_type.get_optionless_update_eq(&inner_left, &inner_right);
+                Self::get_option_eq(inner, left, right)
+            }
+            _ => self.get_data_eq(left, right),
+        }
+    }
+
+    pub fn get_update_eq(&self, left: &TokenStream2, right: &TokenStream2) -> TokenStream2 {
+        match self {
+            Self::Type(_) | Self::List(_) | Self::Map(_, _) => {
+                let inner = self.get_optionless_update_eq(&quote! { left }, &quote! { right });
+                Self::get_option_eq(inner, left, right)
+            }
+            _ => self.get_optionless_update_eq(left, right),
+        }
+    }
+}
+
+/// Last segment of a type path with its type arguments, e.g. `HashMap` and `[K, V]`.
+fn get_path_segment(ty: &Type) -> Option<(&Ident, Vec<&Type>)> {
+    match ty {
+        Type::Group(group) => get_path_segment(&group.elem),
+        Type::Paren(paren) => get_path_segment(&paren.elem),
+        Type::Path(path) => {
+            let last = path.path.segments.last()?;
+            let args = match &last.arguments {
+                PathArguments::AngleBracketed(args) => args
+                    .args
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            Some((&last.ident, args))
+        }
+        _ => None,
+    }
+}
+
+fn is_boxed(ty: &Type) -> bool {
+    matches!(get_path_segment(ty), Some((ident, args)) if ident == "Box" && args.len() == 1)
+}
+
+/// Whether `ty` is `Self`, the struct `name` or a box of either.
+fn is_self_reference(ty: &Type, name: &Ident) -> bool {
+    match get_path_segment(ty) {
+        Some((ident, args)) if ident == "Box" && args.len() == 1 => {
+            is_self_reference(args[0], name)
+        }
+        Some((ident, args)) => args.is_empty() && (ident == name || ident == "Self"),
+        None => false,
+    }
+}
@@ -4,30 +4,101 @@ extern crate proc_macro;
 extern crate lazy_static;
 
 mod ast;
+mod schemalang;
 mod spatial_component;
 mod spatial_enum;
+mod spatial_schema;
 mod spatial_type;
 //mod utils;
 
 use crate::spatial_component::generate_component;
 use crate::spatial_enum::generate_enum;
+use crate::spatial_schema::{generate_include_schema, generate_spatial_schema};
 use crate::spatial_type::generate_type;
 use proc_macro::TokenStream;
 
 #[macro_use]
 extern crate quote;
 
-#[proc_macro_derive(SpatialComponent, attributes(id, field_id, spatial_type))]
+/// Implements `SchemaType` and `SchemaComponent` for a struct with an `#[id(N)]`, like
+/// [`SpatialType`](derive.SpatialType.html) does for types.
+///
+/// With the `sdk` feature the component gets the vtable of `spatialos_sdk`, whose entry points
+/// don't unwind across the FFI boundary: data failing to deserialize or to serialize aborts the
+/// process, unless the component opts into its default value with `default_on_error`. With the `registry` feature it registers itself at
+/// link time in `spatialos_macro_runtime::registry`, and with the `snapshot` feature in
+/// `spatialos_macro_runtime::snapshot` as well.
+#[proc_macro_derive(SpatialComponent, attributes(id, field_id, spatial_type, spatial))]
 pub fn spatial_component(item: TokenStream) -> TokenStream {
     generate_component(item)
 }
 
-#[proc_macro_derive(SpatialType, attributes(field_id, spatial_type))]
+/// Implements `SchemaType` for a struct whose fields carry a `#[field_id(N)]` and a
+/// `#[spatial_type("...")]`, and generates its `FooData` and `FooUpdate` structs, their
+/// builders, a `FooView` reading the fields on access and `From` conversions between `Foo`
+/// and `FooData`.
+///
+/// The declared type of each field must be the Rust type of its schema type, e.g. `f64` for
+/// `double` or `Vec<u32>` for `list<uint32>`, and `type` and `enum` fields must point to types
+/// deriving `SpatialType` and `SpatialEnum`. A type can refer to itself through
+/// `Option<Box<Self>>`, lists or maps.
+///
+/// Options of the struct, in `#[spatial(...)]`:
+///
+/// - `package = "improbable"` and `name = "Position"` set the schema name, which defaults to
+///   the Rust name.
+/// - `strict` makes `strict_data_deserialize` check the number of values of every field, and
+///   reject the field ids the type doesn't declare in objects listing them, like `MemoryObject`.
+/// - `tracked` generates a `TrackedFooData` recording the fields changed through its setters.
+/// - `omit_deprecated` stops serializing the `deprecated` fields.
+/// - `query` implements `spatialos_macro_runtime::query::QuerySchema` for the standard library
+///   types queries are made of, e.g. `improbable.ComponentInterest.Query`, which
+///   `include_schema!` sets itself. Other types, or ones lacking the fields of the standard
+///   library, fail to compile.
+/// - `default_on_error` makes the vtable entry points log a failure and use the default value,
+///   instead of aborting the process.
+/// - `test_roundtrip` generates proptest round trips of `FooData` and `FooUpdate` against
+///   `MemoryObject`, with the `roundtrip` feature of the runtime crate.
+///
+/// Options of a field:
+///
+/// - `default = expr` replaces the `Default` of the field, and is read when it is missing.
+/// - `skip` keeps the field in `FooData` without serializing it, needing no `field_id`.
+/// - `into = "f64"` and `with = "path::to::module"` convert between the declared type and the
+///   schema-side type, through `From`/`Into` or `module::serialize` and `module::deserialize`.
+/// - `since = N` and `deprecated` let the field be missing when deserializing.
+///
+/// With the `serde` feature, `FooData` and `FooUpdate` derive `Serialize` and `Deserialize`,
+/// and with the `json` feature `FooData` converts to and from snapshot JSON.
+#[proc_macro_derive(SpatialType, attributes(field_id, spatial_type, spatial))]
 pub fn spatial_type(item: TokenStream) -> TokenStream {
     generate_type(item)
 }
 
-#[proc_macro_derive(SpatialEnum, attributes(value))]
+/// Implements `SchemaEnum` and `TryFrom<u32>` for a fieldless enum whose variants carry a
+/// `#[value(N)]`. Fields of the enum default to its first value, which values unknown to the
+/// enum read as outside of the fallible deserializers.
+///
+/// The schema name of the enum is set with `package` and `name` like for
+/// [`SpatialType`](derive.SpatialType.html), and that of a variant with
+/// `#[spatial(name = "HTTP_SERVER")]`. With the `serde` feature, values are serialized by
+/// variant name.
+#[proc_macro_derive(SpatialEnum, attributes(value, spatial))]
 pub fn spatial_enum(item: TokenStream) -> TokenStream {
     generate_enum(item)
 }
+
+/// Expands a `.schema` file, relative to the manifest directory, to its types, components
+/// and enums with the implementations the derives would generate.
+#[proc_macro]
+pub fn include_schema(item: TokenStream) -> TokenStream {
+    generate_include_schema(item)
+}
+
+/// Expands schemalang written inline, e.g.
+/// `spatial_schema! { package game; component Health { id = 1000; float hp = 1; } }`,
+/// like `include_schema!` does for a file.
+#[proc_macro]
+pub fn spatial_schema(item: TokenStream) -> TokenStream {
+    generate_spatial_schema(item)
+}
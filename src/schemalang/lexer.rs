@@ -0,0 +1,218 @@
+use proc_macro2::{Delimiter, Span, TokenStream as TokenStream2, TokenTree};
+use syn::{Lit, Meta, MetaNameValue};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Ident(String),
+    Int(u64),
+    Str(String),
+    Punct(char),
+}
+
+/// A token of schemalang, with the `//` comments right before it kept as documentation.
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+    pub line: Option<usize>,
+    pub docs: Vec<String>,
+}
+
+const PUNCTS: &str = "{}[]()<>;=,.";
+
+/// Splits the text of a schema file into tokens, errors being reported at `span`.
+pub fn lex(source: &str, span: Span) -> syn::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut docs = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+    while let Some(&c) = chars.peek() {
+        let token_line = line;
+        let kind = if c == '\n' {
+            chars.next();
+            line += 1;
+            continue;
+        } else if c.is_whitespace() {
+            chars.next();
+            continue;
+        } else if c == '/' {
+            chars.next();
+            match chars.next() {
+                Some('/') => {
+                    let mut comment = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c == '\n' {
+                            break;
+                        }
+                        comment.push(c);
+                        chars.next();
+                    }
+                    docs.push(comment);
+                }
+                Some('*') => {
+                    let mut previous = '\0';
+                    loop {
+                        match chars.next() {
+                            Some('/') if previous == '*' => break,
+                            Some(c) => {
+                                if c == '\n' {
+                                    line += 1;
+                                }
+                                previous = c;
+                            }
+                            None => return Err(error(span, token_line, "unterminated comment")),
+                        }
+                    }
+                }
+                _ => return Err(error(span, token_line, "unexpected '/'")),
+            }
+            continue;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                ident.push(c);
+                chars.next();
+            }
+            TokenKind::Ident(ident)
+        } else if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(&c) = chars.peek() {
+                if !c.is_ascii_digit() {
+                    break;
+                }
+                digits.push(c);
+                chars.next();
+            }
+            let value = digits
+                .parse()
+                .map_err(|_| error(span, token_line, "integer out of range"))?;
+            TokenKind::Int(value)
+        } else if c == '"' {
+            chars.next();
+            let mut string = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => string.extend(chars.next()),
+                    Some('\n') | None => {
+                        return Err(error(span, token_line, "unterminated string"))
+                    }
+                    Some(c) => string.push(c),
+                }
+            }
+            TokenKind::Str(string)
+        } else if PUNCTS.contains(c) {
+            chars.next();
+            TokenKind::Punct(c)
+        } else {
+            return Err(error(
+                span,
+                token_line,
+                &format!("unexpected character '{}'", c),
+            ));
+        };
+        tokens.push(Token {
+            kind,
+            span,
+            line: Some(token_line),
+            docs: std::mem::take(&mut docs),
+        });
+    }
+    Ok(tokens)
+}
+
+/// Converts the tokens of an inline schema, in which `///` comments are the documentation.
+pub fn from_tokens(input: TokenStream2) -> syn::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut docs = Vec::new();
+    push_tokens(input, &mut tokens, &mut docs)?;
+    Ok(tokens)
+}
+
+fn push_tokens(
+    input: TokenStream2,
+    tokens: &mut Vec<Token>,
+    docs: &mut Vec<String>,
+) -> syn::Result<()> {
+    let mut input = input.into_iter().peekable();
+    while let Some(tree) = input.next() {
+        let span = tree.span();
+        let kind = match tree {
+            TokenTree::Punct(punct) if punct.as_char() == '#' => {
+                match input.next() {
+                    Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => {
+                        docs.push(get_doc(group.stream(), group.span())?);
+                    }
+                    _ => return Err(syn::Error::new(span, "expected a doc comment")),
+                }
+                continue;
+            }
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Brace => ('{', '}'),
+                    Delimiter::Bracket => ('[', ']'),
+                    Delimiter::Parenthesis => ('(', ')'),
+                    Delimiter::None => {
+                        push_tokens(group.stream(), tokens, docs)?;
+                        continue;
+                    }
+                };
+                tokens.push(Token {
+                    kind: TokenKind::Punct(open),
+                    span: group.span_open(),
+                    line: None,
+                    docs: std::mem::take(docs),
+                });
+                push_tokens(group.stream(), tokens, docs)?;
+                tokens.push(Token {
+                    kind: TokenKind::Punct(close),
+                    span: group.span_close(),
+                    line: None,
+                    docs: Vec::new(),
+                });
+                continue;
+            }
+            TokenTree::Ident(ident) => TokenKind::Ident(ident.to_string()),
+            TokenTree::Punct(punct) if PUNCTS.contains(punct.as_char()) => {
+                TokenKind::Punct(punct.as_char())
+            }
+            TokenTree::Punct(punct) => {
+                return Err(syn::Error::new(
+                    span,
+                    format!("unexpected character '{}'", punct.as_char()),
+                ))
+            }
+            TokenTree::Literal(literal) => match Lit::new(literal) {
+                Lit::Int(int) => TokenKind::Int(int.base10_parse()?),
+                Lit::Str(string) => TokenKind::Str(string.value()),
+                _ => return Err(syn::Error::new(span, "expected an integer or a string")),
+            },
+        };
+        tokens.push(Token {
+            kind,
+            span,
+            line: None,
+            docs: std::mem::take(docs),
+        });
+    }
+    Ok(())
+}
+
+/// The text of `doc = "..."`, inside the brackets of a doc attribute.
+fn get_doc(input: TokenStream2, span: Span) -> syn::Result<String> {
+    match syn::parse2::<Meta>(input)? {
+        Meta::NameValue(MetaNameValue {
+            path,
+            lit: Lit::Str(doc),
+            ..
+        }) if path.is_ident("doc") => Ok(doc.value()),
+        _ => Err(syn::Error::new(span, "expected a doc comment")),
+    }
+}
+
+fn error(span: Span, line: usize, message: &str) -> syn::Error {
+    syn::Error::new(span, format!("line {}: {}", line, message))
+}
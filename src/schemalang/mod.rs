@@ -0,0 +1,617 @@
+//! Parses schemalang and expands it to the annotated items the derives take as input.
+
+mod lexer;
+mod parser;
+
+use std::collections::HashMap;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{Ident, ItemEnum, ItemStruct, Type};
+
+use crate::ast::{query::QUERY_SCHEMA_NAMES, EnumAST, SpatialType, StructAST};
+
+pub use lexer::{from_tokens, lex};
+pub use parser::Parser;
+
+#[derive(Debug, Default)]
+pub struct SchemaFile {
+    pub package: Option<String>,
+    pub imports: Vec<String>,
+    pub items: Vec<SchemaItem>,
+}
+
+#[derive(Debug)]
+pub enum SchemaItem {
+    Type(TypeDef),
+    Component(ComponentDef),
+    Enum(EnumDef),
+}
+
+#[derive(Debug)]
+pub struct TypeDef {
+    pub docs: Vec<String>,
+    pub name: String,
+    pub fields: Vec<FieldDef>,
+    /// Types and enums defined in the type, e.g. `ComponentInterest.Query`.
+    pub nested: Vec<SchemaItem>,
+}
+
+#[derive(Debug)]
+pub struct ComponentDef {
+    pub docs: Vec<String>,
+    pub name: String,
+    pub id: Option<u32>,
+    /// `data SomeType;`, the component having the fields of that type.
+    pub data: Option<String>,
+    pub fields: Vec<FieldDef>,
+}
+
+#[derive(Debug)]
+pub struct EnumDef {
+    pub docs: Vec<String>,
+    pub name: String,
+    pub variants: Vec<VariantDef>,
+}
+
+#[derive(Debug)]
+pub struct VariantDef {
+    pub docs: Vec<String>,
+    pub name: String,
+    pub value: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldDef {
+    pub docs: Vec<String>,
+    pub name: String,
+    pub ty: FieldType,
+    pub id: u32,
+}
+
+#[derive(Debug, Clone)]
+pub enum FieldType {
+    Primitive(String),
+    /// A type or an enum, possibly qualified with its package or outer types.
+    Named(String),
+    List(Box<FieldType>),
+    Option(Box<FieldType>),
+    Map(Box<FieldType>, Box<FieldType>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Type,
+    Enum,
+}
+
+/// Kinds of the types and enums known by their unqualified name, which is also their Rust name.
+pub type Kinds = HashMap<String, Kind>;
+
+pub fn parse(tokens: Vec<lexer::Token>, end: Span) -> syn::Result<SchemaFile> {
+    Parser::new(tokens, end).parse_file()
+}
+
+impl SchemaFile {
+    pub fn collect_kinds(&self, kinds: &mut Kinds) {
+        fn collect(items: &[SchemaItem], kinds: &mut Kinds) {
+            for item in items {
+                match item {
+                    SchemaItem::Type(definition) => {
+                        kinds.insert(definition.name.clone(), Kind::Type);
+                        collect(&definition.nested, kinds);
+                    }
+                    SchemaItem::Component(_) => {}
+                    SchemaItem::Enum(definition) => {
+                        kinds.insert(definition.name.clone(), Kind::Enum);
+                    }
+                }
+            }
+        }
+        collect(&self.items, kinds);
+    }
+
+    fn find_type(&self, path: &str) -> Option<&TypeDef> {
+        fn find<'a>(items: &'a [SchemaItem], name: &str) -> Option<&'a TypeDef> {
+            items.iter().find_map(|item| match item {
+                SchemaItem::Type(definition) if definition.name == name => Some(definition),
+                SchemaItem::Type(definition) => find(&definition.nested, name),
+                _ => None,
+            })
+        }
+        find(&self.items, get_short_name(path))
+    }
+
+    /// Rust items for every type, component and enum of the file with their implementations,
+    /// nested types being flattened next to their outer type.
+    pub fn expand(&self, kinds: &Kinds, span: Span) -> syn::Result<TokenStream2> {
+        let mut expanded = TokenStream2::new();
+        self.expand_items(&self.items, None, kinds, span, &mut expanded)?;
+        Ok(expanded)
+    }
+
+    fn expand_items(
+        &self,
+        items: &[SchemaItem],
+        outer: Option<&str>,
+        kinds: &Kinds,
+        span: Span,
+        expanded: &mut TokenStream2,
+    ) -> syn::Result<()> {
+        for item in items {
+            match item {
+                SchemaItem::Type(definition) => {
+                    let name = get_nested_name(outer, &definition.name);
+                    let item = self.get_struct(
+                        &definition.docs,
+                        &definition.name,
+                        outer.map(|_| name.as_str()),
+                        None,
+                        &definition.fields,
+                        kinds,
+                        span,
+                    )?;
+                    expanded.extend(expand_struct(item));
+                    self.expand_items(&definition.nested, Some(&name), kinds, span, expanded)?;
+                }
+                SchemaItem::Component(definition) => {
+                    let fields = match &definition.data {
+                        Some(data) if definition.fields.is_empty() => {
+                            &self
+                                .find_type(data)
+                                .ok_or_else(|| {
+                                    syn::Error::new(span, format!("unknown data type {}", data))
+                                })?
+                                .fields
+                        }
+                        Some(_) => {
+                            return Err(syn::Error::new(
+                                span,
+                                format!("component {} has both data and fields", definition.name),
+                            ))
+                        }
+                        None => &definition.fields,
+                    };
+                    let item = self.get_struct(
+                        &definition.docs,
+                        &definition.name,
+                        None,
+                        definition.id,
+                        fields,
+                        kinds,
+                        span,
+                    )?;
+                    expanded.extend(expand_struct(item));
+                }
+                SchemaItem::Enum(definition) => {
+                    let name = get_nested_name(outer, &definition.name);
+                    let item = self.get_enum(definition, outer.map(|_| name.as_str()), span)?;
+                    expanded.extend(expand_enum(item));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn get_struct(
+        &self,
+        docs: &[String],
+        name: &str,
+        schema_name: Option<&str>,
+        id: Option<u32>,
+        fields: &[FieldDef],
+        kinds: &Kinds,
+        span: Span,
+    ) -> syn::Result<ItemStruct> {
+        let ident = get_ident(name, span)?;
+        let mut spatial = self.get_spatial_attribute(schema_name);
+        if self.is_query_schema(schema_name.unwrap_or(name)) {
+            spatial.extend(quote! { #[spatial(query)] });
+        }
+        let id = id.map(|id| quote! { #[id(#id)] });
+        let fields = fields
+            .iter()
+            .map(|field| {
+                let docs = &field.docs;
+                let field_name = get_ident(&field.name, span)?;
+                let field_id = field.id;
+                let (marker, ty) = get_field_type(&field.ty, name, kinds, span)?;
+                Ok(quote! {
+                    #(#[doc = #docs])*
+                    #[field_id(#field_id)]
+                    #[spatial_type(#marker)]
+                    pub #field_name: #ty
+                })
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+        syn::parse2(quote! {
+            #(#[doc = #docs])*
+            #id
+            #spatial
+            pub struct #ident {
+                #(#fields,)*
+            }
+        })
+    }
+
+    fn get_enum(
+        &self,
+        definition: &EnumDef,
+        schema_name: Option<&str>,
+        span: Span,
+    ) -> syn::Result<ItemEnum> {
+        let docs = &definition.docs;
+        let ident = get_ident(&definition.name, span)?;
+        let spatial = self.get_spatial_attribute(schema_name);
+        let variants = definition
+            .variants
+            .iter()
+            .map(|variant| {
+                let docs = &variant.docs;
+                let name = get_ident(&to_pascal_case(&variant.name), span)?;
+                let schema_name = &variant.name;
+                let value = variant.value;
+                Ok(quote! {
+                    #(#[doc = #docs])*
+                    #[value(#value)]
+                    #[spatial(name = #schema_name)]
+                    #name
+                })
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+        syn::parse2(quote! {
+            #(#[doc = #docs])*
+            #spatial
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+            pub enum #ident {
+                #(#variants,)*
+            }
+        })
+    }
+
+    /// Whether `name` is one of the standard library types implementing `QuerySchema`.
+    fn is_query_schema(&self, name: &str) -> bool {
+        self.package.as_ref().is_some_and(|package| {
+            QUERY_SCHEMA_NAMES.contains(&format!("{}.{}", package, name).as_str())
+        })
+    }
+
+    fn get_spatial_attribute(&self, schema_name: Option<&str>) -> TokenStream2 {
+        let package = self.package.iter();
+        let name = schema_name.iter();
+        quote! {
+            #[spatial(#(package = #package,)* #(name = #name,)*)]
+        }
+    }
+}
+
+fn get_nested_name(outer: Option<&str>, name: &str) -> String {
+    match outer {
+        Some(outer) => format!("{}.{}", outer, name),
+        None => name.to_owned(),
+    }
+}
+
+fn get_short_name(path: &str) -> &str {
+    path.rsplit('.').next().unwrap_or(path)
+}
+
+fn get_ident(name: &str, span: Span) -> syn::Result<Ident> {
+    let mut ident = syn::parse_str::<Ident>(name)
+        .map_err(|_| syn::Error::new(span, format!("{} is not a valid Rust identifier", name)))?;
+    ident.set_span(span);
+    Ok(ident)
+}
+
+/// Schemalang enum values are written like `AWAITING_WORKER_CONNECTION`.
+fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first)
+                .chain(chars.map(|c| c.to_ascii_lowercase()))
+                .collect::<String>()
+        })
+        .collect()
+}
+
+/// The `#[spatial_type]` marker of a field of the struct `owner` and its type in the annotated
+/// struct, options of the struct itself being boxed.
+fn get_field_type(
+    ty: &FieldType,
+    owner: &str,
+    kinds: &Kinds,
+    span: Span,
+) -> syn::Result<(String, Type)> {
+    let field_type = match ty {
+        FieldType::Primitive(name) if name == "Entity" => {
+            return Err(syn::Error::new(span, "Entity fields are not supported"))
+        }
+        FieldType::Primitive(name) => {
+            let placeholder = syn::parse_str::<Type>("()").unwrap();
+            let rust_type = SpatialType::from_syn(&placeholder, name).get_rust_type();
+            (name.clone(), rust_type)
+        }
+        FieldType::Named(path) => {
+            let name = get_short_name(path);
+            let marker = match kinds.get(name) {
+                Some(Kind::Enum) => "enum",
+                _ => "type",
+            };
+            let ident = get_ident(name, span)?;
+            (marker.to_owned(), syn::parse2(quote! { #ident })?)
+        }
+        FieldType::List(inner) => {
+            let (marker, ty) = get_field_type(inner, owner, kinds, span)?;
+            (
+                format!("list<{}>", marker),
+                syn::parse2(quote! { Vec<#ty> })?,
+            )
+        }
+        FieldType::Option(inner) => {
+            let (marker, ty) = get_field_type(inner, owner, kinds, span)?;
+            let ty = match &**inner {
+                FieldType::Named(path) if get_short_name(path) == owner => {
+                    syn::parse2(quote! { Option<Box<#ty>> })?
+                }
+                _ => syn::parse2(quote! { Option<#ty> })?,
+            };
+            (format!("option<{}>", marker), ty)
+        }
+        FieldType::Map(key, value) => {
+            let (key_marker, key) = get_field_type(key, owner, kinds, span)?;
+            let (value_marker, value) = get_field_type(value, owner, kinds, span)?;
+            (
+                format!("map<{},{}>", key_marker, value_marker),
+                syn::parse2(quote! { std::collections::HashMap<#key, #value> })?,
+            )
+        }
+    };
+    Ok(field_type)
+}
+
+fn expand_struct(mut item: ItemStruct) -> TokenStream2 {
+    let implementations: TokenStream = StructAST::from(&item).into();
+    let implementations = TokenStream2::from(implementations);
+    item.attrs.retain(|attr| attr.path.is_ident("doc"));
+    for field in item.fields.iter_mut() {
+        field.attrs.retain(|attr| attr.path.is_ident("doc"));
+    }
+    quote! {
+        #item
+        #implementations
+    }
+}
+
+fn expand_enum(mut item: ItemEnum) -> TokenStream2 {
+    let implementations: TokenStream = EnumAST::from(&item).into();
+    let implementations = TokenStream2::from(implementations);
+    item.attrs
+        .retain(|attr| attr.path.is_ident("doc") || attr.path.is_ident("derive"));
+    for variant in item.variants.iter_mut() {
+        variant.attrs.retain(|attr| attr.path.is_ident("doc"));
+    }
+    quote! {
+        #item
+        #implementations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(source: &str) -> syn::Result<SchemaFile> {
+        parse(lex(source, Span::call_site())?, Span::call_site())
+    }
+
+    fn marker(file: &SchemaFile, item: usize, field: usize) -> (String, String) {
+        let (name, fields) = match &file.items[item] {
+            SchemaItem::Type(definition) => (&definition.name, &definition.fields),
+            SchemaItem::Component(definition) => (&definition.name, &definition.fields),
+            SchemaItem::Enum(_) => panic!("enums have no fields"),
+        };
+        let mut kinds = Kinds::new();
+        file.collect_kinds(&mut kinds);
+        let (marker, ty) =
+            get_field_type(&fields[field].ty, name, &kinds, Span::call_site()).unwrap();
+        (marker, quote!(#ty).to_string())
+    }
+
+    #[test]
+    fn packages_and_imports_are_read() {
+        let file = parse_str(
+            r#"
+            package improbable.restricted;
+            import "improbable/standard_library.schema";
+            type Empty {}
+            "#,
+        )
+        .unwrap();
+        assert_eq!(file.package.as_deref(), Some("improbable.restricted"));
+        assert_eq!(file.imports, vec!["improbable/standard_library.schema"]);
+        assert_eq!(file.items.len(), 1);
+    }
+
+    #[test]
+    fn commands_events_and_annotations_are_skipped() {
+        let file = parse_str(
+            r#"
+            [improbable.ExampleAnnotation(1, "a")]
+            component Health {
+                id = 1000;
+                [Unit("hp")]
+                float current = 1;
+                command Empty heal(HealRequest);
+                event Empty died;
+                // Upper bound.
+                float max = 2;
+            }
+            "#,
+        )
+        .unwrap();
+        match &file.items[0] {
+            SchemaItem::Component(definition) => {
+                assert_eq!(definition.id, Some(1000));
+                let names = definition.fields.iter().map(|field| &field.name);
+                assert_eq!(names.collect::<Vec<_>>(), vec!["current", "max"]);
+                assert_eq!(definition.fields[1].docs, vec![" Upper bound."]);
+            }
+            item => panic!("expected a component, found {:?}", item),
+        }
+    }
+
+    #[test]
+    fn nested_items_are_kept_in_their_outer_type() {
+        let file = parse_str(
+            r#"
+            type ComponentInterest {
+                type Query {
+                    list<Query> children = 1;
+                }
+                enum Kind { NONE = 0; SOME_MORE = 1; }
+                list<Query> queries = 1;
+                Kind kind = 2;
+            }
+            "#,
+        )
+        .unwrap();
+        match &file.items[0] {
+            SchemaItem::Type(definition) => {
+                assert_eq!(definition.nested.len(), 2);
+                assert_eq!(definition.fields.len(), 2);
+            }
+            item => panic!("expected a type, found {:?}", item),
+        }
+        assert_eq!(
+            marker(&file, 0, 0),
+            ("list<type>".to_owned(), "Vec < Query >".to_owned())
+        );
+        assert_eq!(marker(&file, 0, 1), ("enum".to_owned(), "Kind".to_owned()));
+        assert_eq!(to_pascal_case("SOME_MORE"), "SomeMore");
+        assert_eq!(
+            get_nested_name(Some("ComponentInterest"), "Query"),
+            "ComponentInterest.Query"
+        );
+    }
+
+    #[test]
+    fn options_of_the_type_itself_are_boxed() {
+        let file = parse_str(
+            r#"
+            type Node {
+                option<Node> next = 1;
+                option<improbable.Coordinates> at = 2;
+                map<string, list<Node>> children = 3;
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            marker(&file, 0, 0),
+            (
+                "option<type>".to_owned(),
+                "Option < Box < Node > >".to_owned()
+            )
+        );
+        assert_eq!(
+            marker(&file, 0, 1),
+            (
+                "option<type>".to_owned(),
+                "Option < Coordinates >".to_owned()
+            )
+        );
+        assert_eq!(marker(&file, 0, 2).0, "map<string,list<type>>");
+    }
+
+    #[test]
+    fn every_primitive_has_a_rust_type() {
+        let file = parse_str(
+            r#"
+            type Numbers {
+                sint32 a = 1;
+                sint64 b = 2;
+                fixed32 c = 3;
+                fixed64 d = 4;
+                sfixed32 e = 5;
+                sfixed64 f = 6;
+                EntityId g = 7;
+            }
+            "#,
+        )
+        .unwrap();
+        let types = (0..7)
+            .map(|field| marker(&file, 0, field).1)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                "i32",
+                "i64",
+                "u32",
+                "u64",
+                "i32",
+                "i64",
+                "spatialos_macro_runtime :: schema :: EntityId"
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_schemas_are_read_from_tokens() {
+        let tokens = from_tokens(quote! {
+            package game;
+            /// A position.
+            [improbable.Annotation("a")]
+            type Vec3 { double x = 1; list<float> y = 2; }
+        })
+        .unwrap();
+        let file = parse(tokens, Span::call_site()).unwrap();
+        assert_eq!(file.package.as_deref(), Some("game"));
+        match &file.items[0] {
+            SchemaItem::Type(definition) => {
+                assert_eq!(definition.docs, vec![" A position."]);
+                assert_eq!(definition.fields[1].name, "y");
+            }
+            item => panic!("expected a type, found {:?}", item),
+        }
+        assert_eq!(marker(&file, 0, 1).0, "list<float>");
+
+        let error = from_tokens(quote! { type A { double x = 1.5; } }).unwrap_err();
+        assert_eq!(error.to_string(), "expected an integer or a string");
+        let error = from_tokens(quote! { type A { double x: 1; } }).unwrap_err();
+        assert_eq!(error.to_string(), "unexpected character ':'");
+    }
+
+    #[test]
+    fn invalid_schemas_are_errors() {
+        let message = |source: &str| parse_str(source).err().unwrap().to_string();
+        assert_eq!(
+            message("component Health {\n float hp = 1;\n}"),
+            "line 3: missing component id"
+        );
+        assert_eq!(
+            message("type A { float = 1; }"),
+            "line 1: expected an identifier"
+        );
+        assert_eq!(message("type A {"), "unexpected end of schema");
+        assert_eq!(
+            message("struct A {}"),
+            "line 1: expected `type`, `component` or `enum`"
+        );
+
+        let file = parse_str("type A { Entity e = 1; }").unwrap();
+        let fields = match &file.items[0] {
+            SchemaItem::Type(definition) => &definition.fields,
+            _ => unreachable!(),
+        };
+        let error = get_field_type(&fields[0].ty, "A", &Kinds::new(), Span::call_site());
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "Entity fields are not supported"
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use proc_macro2::Span;
+
+use super::lexer::{Token, TokenKind};
+use super::{
+    ComponentDef, EnumDef, FieldDef, FieldType, SchemaFile, SchemaItem, TypeDef, VariantDef,
+};
+
+const PRIMITIVES: &[&str] = &[
+    "bool", "float", "double", "bytes", "string", "int32", "int64", "uint32", "uint64", "sint32",
+    "sint64", "fixed32", "fixed64", "sfixed32", "sfixed64", "EntityId", "Entity",
+];
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    /// Span of errors at the end of the input.
+    end: Span,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>, end: Span) -> Self {
+        Self {
+            tokens,
+            position: 0,
+            end,
+        }
+    }
+
+    pub fn parse_file(&mut self) -> syn::Result<SchemaFile> {
+        let mut file = SchemaFile::default();
+        while let Some(token) = self.peek() {
+            match &token.kind {
+                TokenKind::Ident(keyword) if keyword == "package" => {
+                    self.next()?;
+                    file.package = Some(self.parse_path()?);
+                    self.expect_punct(';')?;
+                }
+                TokenKind::Ident(keyword) if keyword == "import" => {
+                    self.next()?;
+                    file.imports.push(self.parse_str()?);
+                    self.expect_punct(';')?;
+                }
+                _ => file.items.push(self.parse_item()?),
+            }
+        }
+        Ok(file)
+    }
+
+    fn parse_item(&mut self) -> syn::Result<SchemaItem> {
+        self.skip_annotations()?;
+        let token = self.next()?;
+        match &token.kind {
+            TokenKind::Ident(keyword) if keyword == "type" => {
+                let name = self.parse_ident()?;
+                self.parse_type_body(name, token.docs).map(SchemaItem::Type)
+            }
+            TokenKind::Ident(keyword) if keyword == "component" => {
+                let name = self.parse_ident()?;
+                self.parse_component_body(name, token.docs)
+                    .map(SchemaItem::Component)
+            }
+            TokenKind::Ident(keyword) if keyword == "enum" => {
+                let name = self.parse_ident()?;
+                self.parse_enum_body(name, token.docs).map(SchemaItem::Enum)
+            }
+            _ => Err(self.error(&token, "expected `type`, `component` or `enum`")),
+        }
+    }
+
+    fn parse_type_body(&mut self, name: String, docs: Vec<String>) -> syn::Result<TypeDef> {
+        let mut definition = TypeDef {
+            docs,
+            name,
+            fields: Vec::new(),
+            nested: Vec::new(),
+        };
+        self.expect_punct('{')?;
+        while !self.eat_punct('}') {
+            self.skip_annotations()?;
+            if self.peek_keyword("type") || self.peek_keyword("enum") {
+                definition.nested.push(self.parse_item()?);
+            } else {
+                definition.fields.push(self.parse_field()?);
+            }
+        }
+        Ok(definition)
+    }
+
+    fn parse_component_body(
+        &mut self,
+        name: String,
+        docs: Vec<String>,
+    ) -> syn::Result<ComponentDef> {
+        let mut definition = ComponentDef {
+            docs,
+            name,
+            id: None,
+            data: None,
+            fields: Vec::new(),
+        };
+        self.expect_punct('{')?;
+        while !self.eat_punct('}') {
+            self.skip_annotations()?;
+            if self.peek_keyword("id") {
+                self.next()?;
+                self.expect_punct('=')?;
+                definition.id = Some(self.parse_u32()?);
+                self.expect_punct(';')?;
+            } else if self.peek_keyword("data") {
+                self.next()?;
+                definition.data = Some(self.parse_path()?);
+                self.expect_punct(';')?;
+            } else if self.peek_keyword("command") || self.peek_keyword("event") {
+                // Commands and events are not generated, only the fields of the component are.
+                while !self.eat_punct(';') {
+                    self.next()?;
+                }
+            } else {
+                definition.fields.push(self.parse_field()?);
+            }
+        }
+        if definition.id.is_none() {
+            let token = &self.tokens[self.position - 1];
+            return Err(self.error(token, "missing component id"));
+        }
+        Ok(definition)
+    }
+
+    fn parse_enum_body(&mut self, name: String, docs: Vec<String>) -> syn::Result<EnumDef> {
+        let mut definition = EnumDef {
+            docs,
+            name,
+            variants: Vec::new(),
+        };
+        self.expect_punct('{')?;
+        while !self.eat_punct('}') {
+            self.skip_annotations()?;
+            let docs = self.peek_docs();
+            let name = self.parse_ident()?;
+            self.expect_punct('=')?;
+            let value = self.parse_u32()?;
+            self.expect_punct(';')?;
+            definition.variants.push(VariantDef { docs, name, value });
+        }
+        Ok(definition)
+    }
+
+    fn parse_field(&mut self) -> syn::Result<FieldDef> {
+        let docs = self.peek_docs();
+        let ty = self.parse_field_type()?;
+        let name = self.parse_ident()?;
+        self.expect_punct('=')?;
+        let id = self.parse_u32()?;
+        self.expect_punct(';')?;
+        Ok(FieldDef { docs, name, ty, id })
+    }
+
+    fn parse_field_type(&mut self) -> syn::Result<FieldType> {
+        let name = self.parse_path()?;
+        let ty = match name.as_str() {
+            "list" | "option" => {
+                self.expect_punct('<')?;
+                let inner = Box::new(self.parse_field_type()?);
+                self.expect_punct('>')?;
+                if name == "list" {
+                    FieldType::List(inner)
+                } else {
+                    FieldType::Option(inner)
+                }
+            }
+            "map" => {
+                self.expect_punct('<')?;
+                let key = Box::new(self.parse_field_type()?);
+                self.expect_punct(',')?;
+                let value = Box::new(self.parse_field_type()?);
+                self.expect_punct('>')?;
+                FieldType::Map(key, value)
+            }
+            primitive if PRIMITIVES.contains(&primitive) => FieldType::Primitive(name),
+            _ => FieldType::Named(name),
+        };
+        Ok(ty)
+    }
+
+    /// Annotations like `[improbable.ExampleAnnotation(1)]` are skipped, the documentation
+    /// written before them moving to the item or field they annotate.
+    fn skip_annotations(&mut self) -> syn::Result<()> {
+        let mut docs = Vec::new();
+        while self.peek_punct('[') {
+            let mut depth = 0;
+            loop {
+                let token = self.next()?;
+                docs.extend(token.docs);
+                match token.kind {
+                    TokenKind::Punct('[') => depth += 1,
+                    TokenKind::Punct(']') => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if let Some(token) = self.tokens.get_mut(self.position) {
+            docs.append(&mut token.docs);
+            token.docs = docs;
+        }
+        Ok(())
+    }
+
+    /// A name which may be qualified, e.g. `improbable.Coordinates`.
+    fn parse_path(&mut self) -> syn::Result<String> {
+        let mut path = self.parse_ident()?;
+        while self.eat_punct('.') {
+            path.push('.');
+            path.push_str(&self.parse_ident()?);
+        }
+        Ok(path)
+    }
+
+    fn parse_ident(&mut self) -> syn::Result<String> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Ident(ident) => Ok(ident),
+            _ => Err(self.error(&token, "expected an identifier")),
+        }
+    }
+
+    fn parse_u32(&mut self) -> syn::Result<u32> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Int(value) if value <= u64::from(u32::MAX) => Ok(value as u32),
+            _ => Err(self.error(&token, "expected an unsigned 32 bit integer")),
+        }
+    }
+
+    fn parse_str(&mut self) -> syn::Result<String> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Str(string) => Ok(string),
+            _ => Err(self.error(&token, "expected a string")),
+        }
+    }
+
+    fn expect_punct(&mut self, punct: char) -> syn::Result<()> {
+        let token = self.next()?;
+        if token.kind == TokenKind::Punct(punct) {
+            Ok(())
+        } else {
+            Err(self.error(&token, &format!("expected '{}'", punct)))
+        }
+    }
+
+    fn eat_punct(&mut self, punct: char) -> bool {
+        let found = self.peek_punct(punct);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn peek_punct(&self, punct: char) -> bool {
+        matches!(self.peek(), Some(token) if token.kind == TokenKind::Punct(punct))
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Ident(ident), .. }) if ident == keyword)
+    }
+
+    fn peek_docs(&self) -> Vec<String> {
+        self.peek()
+            .map(|token| token.docs.clone())
+            .unwrap_or_default()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> syn::Result<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| syn::Error::new(self.end, "unexpected end of schema"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn error(&self, token: &Token, message: &str) -> syn::Error {
+        match token.line {
+            Some(line) => syn::Error::new(token.span, format!("line {}: {}", line, message)),
+            None => syn::Error::new(token.span, message),
+        }
+    }
+}
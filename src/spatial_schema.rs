@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{parse_macro_input, LitStr};
+
+use crate::schemalang::{from_tokens, lex, parse, Kinds, SchemaFile};
+
+pub fn generate_include_schema(item: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(item as LitStr);
+    match include_schema(&path) {
+        Ok(expanded) => expanded.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+pub fn generate_spatial_schema(item: TokenStream) -> TokenStream {
+    match spatial_schema(item.into()) {
+        Ok(expanded) => expanded.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Names which are not defined in the inline schema are taken for types.
+fn spatial_schema(input: TokenStream2) -> syn::Result<TokenStream2> {
+    let span = Span::call_site();
+    let file = parse(from_tokens(input)?, span)?;
+    let mut kinds = Kinds::new();
+    file.collect_kinds(&mut kinds);
+    file.expand(&kinds, span)
+}
+
+/// The file is found relative to the manifest directory of the crate using the macro.
+fn include_schema(path: &LitStr) -> syn::Result<TokenStream2> {
+    let span = path.span();
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+    let path = manifest_dir.join(path.value());
+    let file = read_schema(&path, span)?;
+
+    let mut kinds = Kinds::new();
+    let mut visited = HashSet::new();
+    collect_imported_kinds(&file, &path, &manifest_dir, &mut visited, &mut kinds, span)?;
+    file.collect_kinds(&mut kinds);
+
+    let expanded = file.expand(&kinds, span)?;
+    let tracked = path.to_string_lossy();
+    Ok(quote! {
+        const _: &[u8] = include_bytes!(#tracked);
+        #expanded
+    })
+}
+
+fn read_schema(path: &Path, span: Span) -> syn::Result<SchemaFile> {
+    let source = fs::read_to_string(path).map_err(|error| {
+        syn::Error::new(span, format!("Can't read {}: {}", path.display(), error))
+    })?;
+    let tokens = lex(&source, span)
+        .map_err(|error| syn::Error::new(span, format!("{}: {}", path.display(), error)))?;
+    parse(tokens, span)
+        .map_err(|error| syn::Error::new(span, format!("{}: {}", path.display(), error)))
+}
+
+/// Imports are only read to know which names are enums, their items are not generated.
+/// They are looked up next to the importing file, then in the manifest directory, and
+/// skipped when not found, their names then being taken for types.
+fn collect_imported_kinds(
+    file: &SchemaFile,
+    path: &Path,
+    manifest_dir: &Path,
+    visited: &mut HashSet<PathBuf>,
+    kinds: &mut Kinds,
+    span: Span,
+) -> syn::Result<()> {
+    let directory = path.parent().unwrap_or(manifest_dir);
+    for import in &file.imports {
+        let found = [directory.join(import), manifest_dir.join(import)]
+            .iter()
+            .find(|candidate| candidate.is_file())
+            .cloned();
+        if let Some(import_path) = found {
+            if visited.insert(import_path.clone()) {
+                let imported = read_schema(&import_path, span)?;
+                collect_imported_kinds(
+                    &imported,
+                    &import_path,
+                    manifest_dir,
+                    visited,
+                    kinds,
+                    span,
+                )?;
+                imported.collect_kinds(kinds);
+            }
+        }
+    }
+    Ok(())
+}
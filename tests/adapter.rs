@@ -0,0 +1,90 @@
+use spatialos_macro::*;
+use spatialos_macro_runtime::schema::MemoryObject;
+use spatialos_macro_runtime::{SchemaObject, SchemaType};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Meters(pub f64);
+
+impl From<f64> for Meters {
+    fn from(value: f64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Meters> for f64 {
+    fn from(value: Meters) -> Self {
+        value.0
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serialization {
+    use super::Meters;
+    use spatialos_macro_runtime::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    impl Serialize for Meters {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Meters {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            f64::deserialize(deserializer).map(Meters)
+        }
+    }
+}
+
+mod centimeters {
+    pub fn serialize(value: &u64) -> u32 {
+        (*value / 10) as u32
+    }
+
+    pub fn deserialize(value: u32) -> u64 {
+        u64::from(value) * 10
+    }
+}
+
+#[derive(SpatialType)]
+pub struct Thing {
+    #[field_id(1u32)]
+    #[spatial_type("double")]
+    #[spatial(into = "f64", default = Meters(5.0))]
+    pub dist: Meters,
+    #[field_id(2u32)]
+    #[spatial_type("uint32")]
+    #[spatial(with = "centimeters")]
+    pub size: u64,
+    #[field_id(3u32)]
+    #[spatial_type("list<double>")]
+    #[spatial(into = "Vec<f64>")]
+    pub marks: Vec<f64>,
+}
+
+#[test]
+fn adapters_convert_values() {
+    let mut data = ThingData {
+        dist: Meters(2.0),
+        size: 120,
+        marks: vec![1.0],
+    };
+    let mut object = MemoryObject::new();
+    Thing::data_serialize(&mut data, &mut object);
+    assert_eq!(object.get_double(1), 2.0);
+    assert_eq!(object.get_uint32(2), 12);
+
+    let read = Thing::data_deserialize(&mut object);
+    assert_eq!(read.dist, Meters(2.0));
+    assert_eq!(read.size, 120);
+}
+
+#[test]
+fn defaults_agree_through_adapters() {
+    assert_eq!(ThingData::default().dist, Meters(5.0));
+    assert_eq!(ThingUpdate::default().dist, 5.0);
+
+    let mut object = MemoryObject::new();
+    let view = ThingView::new(&mut object);
+    assert_eq!(view.dist(), 5.0);
+    assert_eq!(Thing::data_deserialize(&mut object).dist, Meters(5.0));
+}
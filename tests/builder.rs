@@ -0,0 +1,55 @@
+mod common;
+
+use common::*;
+use spatialos_macro::*;
+
+#[derive(SpatialType)]
+pub struct Label {
+    #[field_id(1u32)]
+    #[spatial_type("string")]
+    pub text: String,
+    #[field_id(2u32)]
+    #[spatial_type("uint32")]
+    #[spatial(default = 12)]
+    pub size: u32,
+    #[field_id(3u32)]
+    #[spatial_type("list<string>")]
+    pub lines: Vec<String>,
+}
+
+#[test]
+fn data_builders_start_from_defaults() {
+    let data = LabelData::builder().text("hi".to_owned()).build();
+    assert_eq!(data.text, "hi");
+    assert_eq!(data.size, 12);
+    assert!(data.lines.is_empty());
+
+    let data = LabelData::builder()
+        .lines(vec!["a".to_owned()])
+        .size(3)
+        .text("hi".to_owned())
+        .build();
+    assert_eq!(data.size, 3);
+    assert_eq!(data.lines, vec!["a"]);
+}
+
+#[test]
+fn data_builders_set_every_required_field() {
+    let data = CoordinatesData::builder().z(3.0).x(1.0).y(2.0).build();
+    assert_same_coordinates(&data, &coordinates(1.0, 2.0, 3.0));
+}
+
+#[test]
+fn update_builders_only_set_the_given_fields() {
+    let update = SampleUpdate::builder()
+        .count(4)
+        .at(coordinates(1.0, 0.0, 0.0).into())
+        .nick(Some("elm".to_owned()))
+        .build();
+    assert_eq!(update.count, 4);
+    assert_eq!(update.at.unwrap().x, 1.0);
+    assert_eq!(update.nick.as_deref(), Some("elm"));
+    assert!(update.path.is_none());
+    assert!(update.weights.is_none());
+    assert!(update.tags.is_none());
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::*;
+use spatialos_macro::*;
+use spatialos_macro_runtime::bundle::{
+    BundleEntry, EnumValueDefinition, FieldDefinition, FieldType, ValueType,
+};
+
+#[derive(SpatialEnum, Clone, Debug)]
+#[spatial(package = "net")]
+pub enum Protocol {
+    #[value(0u32)]
+    #[spatial(name = "HTTPServer")]
+    HttpServer,
+    #[value(2u32)]
+    Raw,
+}
+
+spatial_schema! {
+    package net;
+
+    enum Transport {
+        KCP_V2 = 0;
+        TCP = 1;
+    }
+}
+
+fn values(entry: BundleEntry) -> Vec<EnumValueDefinition> {
+    match entry {
+        BundleEntry::Enum(definition) => definition.values,
+        entry => panic!("expected an enum, found {:?}", entry),
+    }
+}
+
+#[test]
+fn enum_values_keep_their_schema_name() {
+    assert_eq!(
+        values(Protocol::schema_bundle_entry()),
+        vec![
+            EnumValueDefinition {
+                name: "HTTPServer",
+                value: 0
+            },
+            EnumValueDefinition {
+                name: "Raw",
+                value: 2
+            },
+        ]
+    );
+    let names = values(Transport::schema_bundle_entry())
+        .into_iter()
+        .map(|value| value.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["KCP_V2", "TCP"]);
+}
+
+#[test]
+fn fields_are_described() {
+    let definition = match Sample::schema_bundle_entry() {
+        BundleEntry::Component(definition) => definition,
+        entry => panic!("expected a component, found {:?}", entry),
+    };
+    assert_eq!(definition.qualified_name, "Sample");
+    assert_eq!(definition.component_id, 1000);
+    assert_eq!(
+        definition.fields[4],
+        FieldDefinition {
+            name: "at",
+            field_id: 5,
+            ty: FieldType::Singular(ValueType::Type("Coordinates")),
+        }
+    );
+    assert_eq!(
+        definition.fields[8].ty,
+        FieldType::Map(
+            ValueType::Primitive("string"),
+            ValueType::Primitive("double")
+        )
+    );
+    assert_eq!(
+        definition.fields[10].ty,
+        FieldType::List(ValueType::Enum("Color"))
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn entries_are_assembled_into_a_bundle() {
+    use spatialos_macro_runtime::bundle::to_json;
+
+    let bundle = to_json(&[
+        Protocol::schema_bundle_entry(),
+        Coordinates::schema_bundle_entry(),
+    ]);
+    let enums = &bundle["v1"]["enumDefinitions"];
+    assert_eq!(enums[0]["qualifiedName"], "net.Protocol");
+    assert_eq!(enums[0]["valueDefinitions"][0]["name"], "HTTPServer");
+    let types = &bundle["v1"]["typeDefinitions"];
+    assert_eq!(types[0]["fieldDefinitions"][2]["name"], "z");
+    assert_eq!(
+        types[0]["fieldDefinitions"][2]["singularType"]["type"]["primitive"],
+        "double"
+    );
+}
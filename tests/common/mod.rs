@@ -0,0 +1,105 @@
+//! Types shared by the integration tests, which run the expanded code against `MemoryObject`.
+#![allow(dead_code)]
+
+use spatialos_macro::*;
+use std::collections::HashMap;
+
+#[derive(SpatialEnum, Clone, Debug, PartialEq)]
+pub enum Color {
+    #[value(0u32)]
+    Red,
+    #[value(3u32)]
+    Blue,
+}
+
+#[derive(SpatialType)]
+pub struct Coordinates {
+    #[field_id(1u32)]
+    #[spatial_type("double")]
+    pub x: f64,
+    #[field_id(2u32)]
+    #[spatial_type("double")]
+    pub y: f64,
+    #[field_id(3u32)]
+    #[spatial_type("double")]
+    pub z: f64,
+}
+
+#[derive(SpatialComponent)]
+#[id(1000u32)]
+pub struct Sample {
+    #[field_id(1u32)]
+    #[spatial_type("uint32")]
+    pub count: u32,
+    #[field_id(2u32)]
+    #[spatial_type("string")]
+    pub name: String,
+    #[field_id(3u32)]
+    #[spatial_type("bytes")]
+    pub blob: Vec<u8>,
+    #[field_id(4u32)]
+    #[spatial_type("enum")]
+    pub color: Color,
+    #[field_id(5u32)]
+    #[spatial_type("type")]
+    pub at: Coordinates,
+    #[field_id(6u32)]
+    #[spatial_type("list<type>")]
+    pub path: Vec<Coordinates>,
+    #[field_id(7u32)]
+    #[spatial_type("list<string>")]
+    pub tags: Vec<String>,
+    #[field_id(8u32)]
+    #[spatial_type("option<string>")]
+    pub nick: Option<String>,
+    #[field_id(9u32)]
+    #[spatial_type("map<string,double>")]
+    pub weights: HashMap<String, f64>,
+    #[field_id(10u32)]
+    #[spatial_type("EntityId")]
+    pub target: i64,
+    #[field_id(11u32)]
+    #[spatial_type("list<enum>")]
+    pub colors: Vec<Color>,
+}
+
+pub fn coordinates(x: f64, y: f64, z: f64) -> CoordinatesData {
+    CoordinatesData { x, y, z }
+}
+
+pub fn sample() -> SampleData {
+    SampleData {
+        count: 7,
+        name: "tree".to_owned(),
+        blob: vec![0, 1, 255],
+        color: Color::Blue,
+        at: coordinates(1.0, 2.0, 3.0),
+        path: vec![coordinates(4.0, 5.0, 6.0), coordinates(-1.0, 0.5, 0.0)],
+        tags: vec!["a".to_owned(), "b".to_owned()],
+        nick: Some("oak".to_owned()),
+        weights: vec![("w".to_owned(), 0.5)].into_iter().collect(),
+        target: -12,
+        colors: vec![Color::Blue, Color::Red],
+    }
+}
+
+pub fn assert_same_coordinates(left: &CoordinatesData, right: &CoordinatesData) {
+    assert_eq!((left.x, left.y, left.z), (right.x, right.y, right.z));
+}
+
+pub fn assert_same_sample(left: &SampleData, right: &SampleData) {
+    assert_eq!(left.count, right.count);
+    assert_eq!(left.name, right.name);
+    assert_eq!(left.blob, right.blob);
+    assert_eq!(left.color, right.color);
+    assert_same_coordinates(&left.at, &right.at);
+    assert_eq!(left.path.len(), right.path.len());
+    for (left, right) in left.path.iter().zip(&right.path) {
+        assert_same_coordinates(left, right);
+    }
+    assert_eq!(left.tags, right.tags);
+    assert_eq!(left.nick, right.nick);
+    assert_eq!(left.weights, right.weights);
+    assert_eq!(left.target, right.target);
+    assert_eq!(left.colors, right.colors);
+}
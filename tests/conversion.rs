@@ -0,0 +1,27 @@
+mod common;
+
+use common::*;
+
+fn annotated() -> Sample {
+    let data = sample();
+    Sample::from(data)
+}
+
+#[test]
+fn annotated_structs_convert_into_data() {
+    let value = annotated();
+    assert_eq!(value.count, 7);
+    assert_eq!(value.at.y, 2.0);
+    assert_eq!(value.path[1].x, -1.0);
+
+    let data = SampleData::from(value);
+    assert_same_sample(&data, &sample());
+}
+
+#[test]
+fn nested_types_use_their_own_conversions() {
+    let value = Coordinates::from(coordinates(1.0, 2.0, 3.0));
+    assert_eq!((value.x, value.y, value.z), (1.0, 2.0, 3.0));
+    let data: CoordinatesData = value.into();
+    assert_same_coordinates(&data, &coordinates(1.0, 2.0, 3.0));
+}
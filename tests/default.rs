@@ -0,0 +1,96 @@
+mod common;
+
+use common::*;
+use spatialos_macro::*;
+use spatialos_macro_runtime::schema::{MemoryObject, SchemaErrorKind};
+use spatialos_macro_runtime::{SchemaEnum, SchemaObject, SchemaType};
+use std::convert::TryFrom;
+
+#[derive(SpatialType)]
+pub struct Settings {
+    #[field_id(1u32)]
+    #[spatial_type("int64")]
+    #[spatial(default = 40 + 2)]
+    pub answer: i64,
+    #[field_id(2u32)]
+    #[spatial_type("enum")]
+    #[spatial(default = Color::Blue)]
+    pub color: Color,
+    #[field_id(3u32)]
+    #[spatial_type("enum")]
+    pub fallback: Color,
+    #[field_id(4u32)]
+    #[spatial_type("string")]
+    #[spatial(default = "none".to_owned())]
+    pub label: String,
+    #[field_id(5u32)]
+    #[spatial_type("list<uint32>")]
+    pub ids: Vec<u32>,
+}
+
+#[derive(SpatialEnum, Debug)]
+pub enum Empty {}
+
+/// An enum value written without checking it.
+struct RawEnum(u32);
+
+impl From<&RawEnum> for u32 {
+    fn from(value: &RawEnum) -> Self {
+        value.0
+    }
+}
+
+#[test]
+fn enums_default_to_their_first_value() {
+    assert_eq!(SampleData::default().color, Color::Red);
+    assert_eq!(SampleUpdate::default().color, Color::Red);
+}
+
+#[test]
+fn fields_use_their_default() {
+    let data = SettingsData::default();
+    assert_eq!(data.answer, 42);
+    assert_eq!(data.color, Color::Blue);
+    assert_eq!(data.fallback, Color::Red);
+    assert_eq!(data.label, "none");
+    assert!(data.ids.is_empty());
+
+    let update = SettingsUpdate::default();
+    assert_eq!(update.answer, 42);
+    assert_eq!(update.color, Color::Blue);
+    assert!(update.ids.is_none());
+}
+
+#[test]
+fn missing_fields_read_as_their_default() {
+    let mut object = MemoryObject::new();
+    object.add_int64(1, 7);
+    let data = Settings::data_deserialize(&mut object);
+    assert_eq!(data.answer, 7);
+    assert_eq!(data.color, Color::Blue);
+    assert_eq!(data.fallback, Color::Red);
+    assert_eq!(data.label, "none");
+}
+
+#[test]
+fn unknown_enum_values_read_as_the_first_value() {
+    assert_eq!(Color::try_from(3).unwrap(), Color::Blue);
+    assert_eq!(
+        Color::try_from(2).unwrap_err().kind,
+        SchemaErrorKind::InvalidEnum(2)
+    );
+    assert_eq!(Color::from_value(2), Color::Red);
+
+    let mut object = MemoryObject::new();
+    object.add_enum(3, &RawEnum(2));
+    assert_eq!(Settings::data_deserialize(&mut object).fallback, Color::Red);
+}
+
+#[test]
+fn enums_may_have_no_value() {
+    assert!(<Empty as SchemaEnum>::VALUES.is_empty());
+    assert_eq!(
+        Empty::try_from(0).unwrap_err().kind,
+        SchemaErrorKind::InvalidEnum(0)
+    );
+}
@@ -0,0 +1,81 @@
+use spatialos_macro::*;
+use spatialos_macro_runtime::schema::{AddedField, MemoryObject};
+use spatialos_macro_runtime::{SchemaObject, SchemaType};
+
+#[derive(SpatialType)]
+#[spatial(strict)]
+pub struct Profile {
+    #[field_id(1u32)]
+    #[spatial_type("string")]
+    pub name: String,
+    #[field_id(2u32)]
+    #[spatial_type("uint32")]
+    #[spatial(since = 2, default = 1)]
+    pub level: u32,
+    #[field_id(3u32)]
+    #[spatial_type("string")]
+    #[spatial(deprecated)]
+    pub nickname: String,
+}
+
+#[derive(SpatialType)]
+#[spatial(omit_deprecated)]
+pub struct TrimmedProfile {
+    #[field_id(1u32)]
+    #[spatial_type("string")]
+    pub name: String,
+    #[field_id(3u32)]
+    #[spatial_type("string")]
+    #[spatial(deprecated)]
+    pub nickname: String,
+}
+
+#[test]
+fn compatibility_lists_added_and_removed_fields() {
+    let compatibility = Profile::COMPATIBILITY;
+    assert_eq!(
+        compatibility.added,
+        &[AddedField {
+            field_id: 2,
+            since: 2
+        }]
+    );
+    assert_eq!(compatibility.added_after(1).collect::<Vec<_>>(), vec![2]);
+    assert_eq!(compatibility.added_after(2).count(), 0);
+    assert!(compatibility.is_removed(3));
+    assert!(!compatibility.omits_removed);
+
+    let compatibility = TrimmedProfile::COMPATIBILITY;
+    assert!(compatibility.omits_removed);
+    assert_eq!(compatibility.removed, &[3]);
+}
+
+#[test]
+fn missing_new_and_deprecated_fields_are_tolerated() {
+    let mut object = MemoryObject::new();
+    object.add_string(1, "old");
+    let data = Profile::strict_data_deserialize(&mut object).unwrap();
+    assert_eq!(data.name, "old");
+    assert_eq!(data.level, 1);
+    assert!(data.nickname.is_empty());
+}
+
+#[test]
+fn deprecated_fields_can_be_omitted() {
+    let mut object = MemoryObject::new();
+    let mut data = ProfileData {
+        name: "a".to_owned(),
+        level: 3,
+        nickname: "b".to_owned(),
+    };
+    Profile::data_serialize(&mut data, &mut object);
+    assert_eq!(object.field_ids(), vec![1, 2, 3]);
+
+    let mut object = MemoryObject::new();
+    let mut data = TrimmedProfileData {
+        name: "a".to_owned(),
+        nickname: "b".to_owned(),
+    };
+    TrimmedProfile::data_serialize(&mut data, &mut object);
+    assert_eq!(object.field_ids(), vec![1]);
+}
@@ -0,0 +1,95 @@
+use spatialos_macro::*;
+use spatialos_macro_runtime::schema::MemoryObject;
+use spatialos_macro_runtime::{SchemaComponent, SchemaObject, SchemaType};
+use std::convert::TryFrom;
+
+include_schema!("tests/schema/numbers.schema");
+
+#[derive(SpatialType)]
+#[spatial(test_roundtrip)]
+pub struct Derived {
+    #[field_id(1u32)]
+    #[spatial_type("sint32")]
+    pub a: i32,
+    #[field_id(2u32)]
+    #[spatial_type("fixed64")]
+    pub b: u64,
+    #[field_id(3u32)]
+    #[spatial_type("list<sfixed32>")]
+    pub c: Vec<i32>,
+    #[field_id(4u32)]
+    #[spatial_type("option<sint64>")]
+    pub d: Option<i64>,
+    #[field_id(5u32)]
+    #[spatial_type("map<fixed32,sfixed64>")]
+    pub e: std::collections::HashMap<u32, i64>,
+}
+
+fn encodings() -> EncodingsData {
+    EncodingsData {
+        a: -1,
+        b: i64::MIN,
+        c: u32::MAX,
+        d: 7,
+        e: -8,
+        f: 9,
+        g: vec![-2, 3],
+        h: Some(-4),
+        i: vec![(5, -6)].into_iter().collect(),
+    }
+}
+
+#[test]
+fn items_are_generated_from_the_file() {
+    assert_eq!(<Counter as SchemaComponent>::ID, 4000);
+    assert_eq!(<Counter as SchemaType>::SCHEMA_NAME, "test.numbers.Counter");
+    assert_eq!(<Link as SchemaType>::SCHEMA_NAME, "test.numbers.Chain.Link");
+    assert_eq!(Sign::NonNegative, Sign::try_from(1).unwrap());
+}
+
+#[test]
+fn integer_encodings_round_trip() {
+    let mut object = MemoryObject::new();
+    Encodings::data_serialize(&mut encodings(), &mut object);
+    assert_eq!(object.get_sint32(1), -1);
+    assert_eq!(object.get_sint64_count(2), 1);
+    assert_eq!(object.get_sfixed32_list(7).len(), 0);
+    assert_eq!(object.get_sint32_list(7), vec![-2, 3]);
+
+    let mut buffer = MemoryObject::from_buffer(&object.serialize_to_buffer()).unwrap();
+    let read = Encodings::data_deserialize(&mut buffer);
+    let data = encodings();
+    assert_eq!(
+        (read.a, read.b, read.c, read.d, read.e, read.f),
+        (data.a, data.b, data.c, data.d, data.e, data.f)
+    );
+    assert_eq!(read.g, data.g);
+    assert_eq!(read.h, data.h);
+    assert_eq!(read.i, data.i);
+
+    let update = Encodings::update_deserialize(&mut object);
+    assert_eq!(update.g, Some(vec![-2, 3]));
+    assert_eq!(update.h, Some(-4));
+
+    let view = EncodingsView::new(&mut object);
+    assert_eq!(view.d(), 7);
+    assert_eq!(view.h(), Some(-4));
+}
+
+#[test]
+fn self_references_are_boxed() {
+    let mut data = ChainData {
+        links: vec![LinkData {
+            sign: Sign::Negative,
+        }],
+        next: Some(Box::new(ChainData {
+            links: Vec::new(),
+            next: None,
+        })),
+    };
+    let mut object = MemoryObject::new();
+    Chain::data_serialize(&mut data, &mut object);
+    let read = Chain::data_deserialize(&mut object);
+    assert_eq!(read.links[0].sign, Sign::Negative);
+    assert!(read.next.unwrap().next.is_none());
+}
@@ -0,0 +1,71 @@
+#![cfg(feature = "json")]
+
+mod common;
+
+use common::*;
+use spatialos_macro_runtime::json::{JsonError, SnapshotJson, Value};
+
+#[test]
+fn fields_are_keyed_by_id_with_their_type() {
+    let json = sample().to_snapshot_json();
+    assert_eq!(json["1"]["type"], "uint32");
+    assert_eq!(json["1"]["value"], 7);
+    assert_eq!(json["3"]["value"], "AAH/");
+    assert_eq!(json["4"]["type"], "enum");
+    assert_eq!(json["4"]["value"], 3);
+    assert_eq!(json["5"]["value"]["2"]["value"], 2.0);
+    assert_eq!(json["8"]["type"], "option<string>");
+    assert_eq!(json["9"]["value"][0]["key"], "w");
+    assert_eq!(json["10"]["type"], "EntityId");
+}
+
+#[test]
+fn data_round_trip() {
+    let data = sample();
+    let read = SampleData::from_snapshot_json(&data.to_snapshot_json()).unwrap();
+    assert_same_sample(&read, &data);
+}
+
+#[test]
+fn mismatches_are_reported() {
+    let mut json = sample().to_snapshot_json();
+    json["1"]["type"] = "int32".into();
+    assert_eq!(
+        SampleData::from_snapshot_json(&json).unwrap_err(),
+        JsonError::TypeMismatch {
+            field_id: 1,
+            expected: "uint32".to_owned(),
+            found: "int32".to_owned(),
+        }
+    );
+
+    let mut json = sample().to_snapshot_json();
+    json.as_object_mut().unwrap().remove("2");
+    assert_eq!(
+        SampleData::from_snapshot_json(&json).unwrap_err(),
+        JsonError::MissingField(2)
+    );
+    assert!(SampleData::from_snapshot_json(&Value::Null).is_err());
+}
+
+#[test]
+fn non_finite_floats_round_trip() {
+    let mut data = sample();
+    data.at = coordinates(f64::NAN, f64::INFINITY, f64::NEG_INFINITY);
+    let json = data.to_snapshot_json();
+    assert_eq!(json["5"]["value"]["1"]["value"], "NaN");
+    assert_eq!(json["5"]["value"]["2"]["value"], "Infinity");
+    assert_eq!(json["5"]["value"]["3"]["value"], "-Infinity");
+    let read = SampleData::from_snapshot_json(&json).unwrap();
+    assert!(read.at.x.is_nan());
+    assert_eq!(read.at.y, f64::INFINITY);
+    assert_eq!(read.at.z, f64::NEG_INFINITY);
+
+    assert_eq!(Some(f32::NAN).to_json(), "NaN");
+    assert!(Option::<f32>::from_json(&"NaN".into())
+        .unwrap()
+        .unwrap()
+        .is_nan());
+    assert_eq!(f32::from_json(&1.5.into()), Ok(1.5));
+    assert!(f64::from_json(&"nan".into()).is_err());
+}
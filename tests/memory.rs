@@ -0,0 +1,103 @@
+mod common;
+
+use common::*;
+use spatialos_macro_runtime::schema::MemoryObject;
+use spatialos_macro_runtime::{SchemaComponent, SchemaObject, SchemaType};
+
+#[test]
+fn data_is_written_under_field_ids() {
+    let mut data = sample();
+    let mut object = MemoryObject::new();
+    Sample::data_serialize(&mut data, &mut object);
+
+    assert_eq!(object.field_ids(), (1..=11).collect::<Vec<_>>());
+    assert_eq!(object.get_uint32(1), 7);
+    assert_eq!(object.get_string(2), "tree");
+    assert_eq!(object.get_bytes(3), vec![0, 1, 255]);
+    assert_eq!(object.get_enum::<u32>(4), 3);
+    assert_eq!(object.get_object(5).get_double(2), 2.0);
+    assert_eq!(object.get_object_count(6), 2);
+    assert_eq!(object.index_object(6, 1).get_double(1), -1.0);
+    assert_eq!(object.get_string_list(7), vec!["a", "b"]);
+    assert_eq!(object.get_string_count(8), 1);
+    let entry = object.index_object(9, 0);
+    assert_eq!(
+        (entry.get_string(1), entry.get_double(2)),
+        ("w".to_owned(), 0.5)
+    );
+    assert_eq!(object.get_entity_id(10), -12);
+    assert_eq!(object.get_enum_list::<u32>(11), vec![3, 0]);
+}
+
+#[test]
+fn data_round_trip() {
+    let mut data = sample();
+    let mut object = MemoryObject::new();
+    Sample::data_serialize(&mut data, &mut object);
+    assert_same_sample(&Sample::data_deserialize(&mut object), &data);
+}
+
+#[test]
+fn data_buffer_round_trip() {
+    let mut data = sample();
+    let mut object = MemoryObject::new();
+    Sample::data_serialize(&mut data, &mut object);
+
+    let mut read = MemoryObject::from_buffer(&object.serialize_to_buffer()).unwrap();
+    assert_same_sample(&Sample::data_deserialize(&mut read), &data);
+}
+
+#[test]
+fn missing_fields_read_as_empty_values() {
+    let data = Sample::data_deserialize(&mut MemoryObject::new());
+    assert_eq!(data.count, 0);
+    assert_eq!(data.name, "");
+    assert_eq!(data.color, Color::Red);
+    assert_eq!(data.at.x, 0.0);
+    assert!(data.path.is_empty());
+    assert_eq!(data.nick, None);
+    assert!(data.weights.is_empty());
+}
+
+#[test]
+fn update_round_trip() {
+    let mut update = SampleUpdate {
+        count: 3,
+        name: "rock".to_owned(),
+        blob: vec![9],
+        color: Color::Blue,
+        at: Some(CoordinatesUpdate {
+            x: 1.0,
+            y: 0.0,
+            z: -1.0,
+        }),
+        path: None,
+        tags: Some(vec!["c".to_owned()]),
+        nick: None,
+        weights: None,
+        target: 5,
+        colors: Some(vec![Color::Red]),
+    };
+    let mut object = MemoryObject::new();
+    Sample::update_serialize(&mut update, &mut object);
+    assert_eq!(object.field_ids(), vec![1, 2, 3, 4, 5, 7, 10, 11]);
+
+    let mut read = MemoryObject::from_buffer(&object.serialize_to_buffer()).unwrap();
+    let read = Sample::update_deserialize(&mut read);
+    assert_eq!(read.count, 3);
+    assert_eq!(read.name, "rock");
+    assert_eq!(read.blob, vec![9]);
+    assert_eq!(read.color, Color::Blue);
+    assert_eq!(read.at.unwrap().z, -1.0);
+    assert!(read.path.is_none());
+    assert_eq!(read.tags, Some(vec!["c".to_owned()]));
+    assert_eq!(read.nick, None);
+    assert!(read.weights.is_none());
+    assert_eq!(read.target, 5);
+    assert_eq!(read.colors, Some(vec![Color::Red]));
+}
+
+#[test]
+fn component_ids() {
+    assert_eq!(<Sample as SchemaComponent>::ID, 1000);
+}
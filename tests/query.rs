@@ -0,0 +1,118 @@
+use spatialos_macro::*;
+use spatialos_macro_runtime::query::{component, Constraint, EmptyOrError, Query as InterestQuery};
+use spatialos_macro_runtime::schema::MemoryObject;
+use spatialos_macro_runtime::{SchemaComponent, SchemaObject, SchemaType};
+
+include_schema!("tests/schema/improbable/standard_library.schema");
+
+#[derive(SpatialType)]
+#[spatial(package = "game", name = "Coordinates")]
+pub struct Unrelated {
+    #[field_id(7u32)]
+    #[spatial_type("double")]
+    pub x: f64,
+}
+
+fn every_constraint() -> Constraint {
+    Constraint::Sphere {
+        center: [1.0, 2.0, 3.0],
+        radius: 4.0,
+    }
+    .and(Constraint::Cylinder {
+        center: [0.0, -1.0, 0.0],
+        radius: 2.0,
+    })
+    .and(Constraint::Box {
+        center: [5.0, 5.0, 5.0],
+        edge_length: [1.0, 2.0, 3.0],
+    })
+    .and(
+        Constraint::RelativeSphere { radius: 8.0 }
+            .or(Constraint::RelativeCylinder { radius: 9.0 })
+            .or(Constraint::RelativeBox {
+                edge_length: [3.0, 2.0, 1.0],
+            }),
+    )
+    .and(Constraint::EntityId(42))
+    .and(component::<Position>())
+    .and(Constraint::SelfEntity)
+}
+
+#[test]
+fn queries_convert_to_the_derived_data() {
+    let query = InterestQuery::sphere([1.0, 2.0, 3.0], 50.0)
+        .and(component::<Position>())
+        .with_component::<Metadata>()
+        .frequency(10.0);
+    let data = query.to_data::<Query>().unwrap();
+    assert_eq!(data.full_snapshot_result, None);
+    assert_eq!(data.result_component_id, vec![Metadata::ID]);
+    assert_eq!(data.frequency, Some(10.0));
+    assert_eq!(data.constraint.and_constraint.len(), 2);
+    let sphere = data.constraint.and_constraint[0]
+        .sphere_constraint
+        .as_ref()
+        .unwrap();
+    assert_eq!(
+        (sphere.center.x, sphere.center.y, sphere.center.z),
+        (1.0, 2.0, 3.0)
+    );
+    assert_eq!(sphere.radius, 50.0);
+    assert_eq!(
+        data.constraint.and_constraint[1].component_constraint,
+        Some(Position::ID)
+    );
+
+    assert_eq!(InterestQuery::from_data::<Query>(&data), query);
+}
+
+#[test]
+fn every_constraint_roundtrips() {
+    let constraint = every_constraint();
+    assert_eq!(
+        Constraint::from_data::<QueryConstraint>(&constraint.to_data::<QueryConstraint>().unwrap()),
+        constraint
+    );
+}
+
+#[test]
+fn queries_use_the_declared_field_ids() {
+    let query = InterestQuery::new(every_constraint()).frequency(2.0);
+    let mut object = MemoryObject::new();
+    query.serialize::<Query, _>(&mut object).unwrap();
+    assert_eq!(object.field_ids(), vec![1, 2, 4]);
+    assert!(object.get_bool(2));
+    assert_eq!(object.get_object(1).get_object_count(9), 7);
+
+    assert_eq!(InterestQuery::deserialize::<Query, _>(&mut object), query);
+    assert_eq!(
+        InterestQuery::from_data::<Query>(&Query::data_deserialize(&mut object)),
+        query
+    );
+}
+
+#[test]
+fn empty_constraints_read_as_and() {
+    let data = QueryConstraintData::default();
+    assert_eq!(
+        Constraint::from_data::<QueryConstraint>(&data),
+        Constraint::And(Vec::new())
+    );
+}
+
+#[test]
+fn empty_or_constraints_are_rejected() {
+    let constraint = component::<Position>().and(Constraint::Or(Vec::new()));
+    assert_eq!(
+        constraint.to_data::<QueryConstraint>().unwrap_err(),
+        EmptyOrError
+    );
+    let query = InterestQuery::new(constraint);
+    assert_eq!(query.to_data::<Query>().unwrap_err(), EmptyOrError);
+    let mut object = MemoryObject::new();
+    assert_eq!(
+        query.serialize::<Query, _>(&mut object).unwrap_err(),
+        EmptyOrError
+    );
+    assert!(object.field_ids().is_empty());
+}
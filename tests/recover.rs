@@ -0,0 +1,100 @@
+mod common;
+
+use common::*;
+use spatialos_macro::*;
+use spatialos_macro_runtime::schema::{recover, recover_serialize, MemoryObject};
+use spatialos_macro_runtime::{SchemaObject, SchemaType};
+use std::env;
+use std::process::Command;
+
+mod checked {
+    pub fn serialize(value: &u32) -> u32 {
+        assert!(*value < 100, "too large");
+        *value
+    }
+
+    pub fn deserialize(value: u32) -> u32 {
+        value
+    }
+}
+
+#[derive(SpatialType)]
+#[spatial(default_on_error)]
+pub struct Limited {
+    #[field_id(1u32)]
+    #[spatial_type("string")]
+    pub name: String,
+    #[field_id(2u32)]
+    #[spatial_type("uint32")]
+    #[spatial(with = "checked")]
+    pub value: u32,
+}
+
+/// An enum value written without checking it.
+struct RawEnum(u32);
+
+impl From<&RawEnum> for u32 {
+    fn from(value: &RawEnum) -> Self {
+        value.0
+    }
+}
+
+/// Serializes a `Sample` with an enum value that is out of range.
+fn invalid_sample() -> MemoryObject {
+    let mut object = MemoryObject::new();
+    Sample::data_serialize(&mut sample(), &mut object);
+    object.clear_field(4);
+    object.add_enum(4, &RawEnum(2));
+    object
+}
+
+#[test]
+fn invalid_values_are_reported_with_their_path() {
+    let mut object = invalid_sample();
+    let error = Sample::try_deserialize_data(&mut object).unwrap_err();
+    assert_eq!(error.to_string(), "Sample.color: invalid enum value 2");
+}
+
+#[test]
+fn failures_abort_without_default_on_error() {
+    if env::var_os("RECOVER_ABORT").is_some() {
+        let mut object = invalid_sample();
+        recover::<Sample, _, _>(|| Sample::try_deserialize_data(&mut object));
+        return;
+    }
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["--exact", "failures_abort_without_default_on_error"])
+        .args(["--test-threads", "1"])
+        .env("RECOVER_ABORT", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn failures_read_the_default_value_with_default_on_error() {
+    let mut object = MemoryObject::new();
+    object.add_bytes(1, &[255]);
+    let data = recover::<Limited, _, _>(|| Limited::try_deserialize_data(&mut object));
+    assert!(data.name.is_empty());
+    assert_eq!(data.value, 0);
+}
+
+#[test]
+fn panicking_serializers_write_the_default_value_with_default_on_error() {
+    let mut object = MemoryObject::new();
+    let mut data = LimitedData {
+        name: "a".to_owned(),
+        value: 500,
+    };
+    recover_serialize::<Limited, _, _, _>(&mut data, &mut object, Limited::data_serialize);
+    assert_eq!(object.field_ids(), vec![1, 2]);
+    assert!(object.get_string(1).is_empty());
+    assert_eq!(object.get_uint32(2), 0);
+
+    let mut object = MemoryObject::new();
+    data.value = 5;
+    recover_serialize::<Limited, _, _, _>(&mut data, &mut object, Limited::data_serialize);
+    assert_eq!(object.get_string(1), "a");
+    assert_eq!(object.get_uint32(2), 5);
+}
@@ -0,0 +1,50 @@
+use spatialos_macro::*;
+use spatialos_macro_runtime::schema::MemoryObject;
+use spatialos_macro_runtime::{SchemaObject, SchemaType};
+
+#[derive(SpatialType)]
+pub struct Node {
+    #[field_id(1u32)]
+    #[spatial_type("int32")]
+    pub value: i32,
+    #[field_id(2u32)]
+    #[spatial_type("option<type>")]
+    pub next: Option<Box<Self>>,
+    #[field_id(3u32)]
+    #[spatial_type("list<type>")]
+    pub children: Vec<Node>,
+}
+
+fn node(value: i32, next: Option<NodeData>) -> NodeData {
+    NodeData {
+        value,
+        next: next.map(Box::new),
+        children: Vec::new(),
+    }
+}
+
+#[test]
+fn linked_values_round_trip() {
+    let mut data = node(1, Some(node(2, Some(node(3, None)))));
+    data.children.push(node(4, None));
+    let mut object = MemoryObject::new();
+    Node::data_serialize(&mut data, &mut object);
+    assert_eq!(object.get_object(2).get_object(2).get_int32(1), 3);
+
+    let read = Node::data_deserialize(&mut object);
+    let second = read.next.unwrap();
+    assert_eq!(second.value, 2);
+    assert_eq!(second.next.unwrap().value, 3);
+    assert_eq!(read.children[0].value, 4);
+}
+
+#[test]
+fn updates_box_the_nested_value() {
+    let mut object = MemoryObject::new();
+    object.add_int32(1, 1);
+    object.add_object(2).add_int32(1, 2);
+    let update = Node::update_deserialize(&mut object);
+    let next: Box<NodeUpdate> = update.next.unwrap();
+    assert_eq!(next.value, 2);
+    assert!(next.next.is_none());
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "registry")]
+
+mod common;
+
+use spatialos_macro::*;
+use spatialos_macro_runtime::registry;
+
+#[derive(SpatialComponent)]
+#[id(1001u32)]
+#[spatial(package = "test")]
+pub struct Marker {}
+
+#[test]
+fn components_register_themselves() {
+    let ids = registry::components()
+        .iter()
+        .map(|component| component.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![1000, 1001]);
+
+    let marker = registry::find_component(1001).unwrap();
+    assert_eq!(marker.rust_name, "registry::Marker");
+    assert_eq!(marker.schema_name, "test.Marker");
+    assert_eq!(
+        registry::find_component(1000).unwrap().rust_name,
+        "registry::common::Sample"
+    );
+    assert!(registry::find_component(1002).is_none());
+}
+
+#[test]
+fn ids_are_unique() {
+    assert!(registry::find_duplicate_ids().is_empty());
+    assert!(registry::check_unique_ids().is_ok());
+}
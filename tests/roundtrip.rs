@@ -0,0 +1,125 @@
+//! `#[spatial(test_roundtrip)]` generates proptests of its own; these also check the generated
+//! strategies and comparisons, and run the generated values through the buffer encoding.
+
+use spatialos_macro::*;
+use spatialos_macro_runtime::proptest::strategy::{Strategy, ValueTree};
+use spatialos_macro_runtime::proptest::test_runner::TestRunner;
+use spatialos_macro_runtime::schema::MemoryObject;
+use spatialos_macro_runtime::testing::ArbitrarySchema;
+use spatialos_macro_runtime::SchemaType;
+use std::collections::HashMap;
+
+#[derive(SpatialEnum, Clone, Debug)]
+pub enum Shape {
+    #[value(1u32)]
+    Round,
+    #[value(4u32)]
+    Square,
+}
+
+#[derive(SpatialType)]
+#[spatial(test_roundtrip)]
+pub struct Inner {
+    #[field_id(1u32)]
+    #[spatial_type("float")]
+    pub ratio: f32,
+    #[field_id(2u32)]
+    #[spatial_type("list<double>")]
+    pub samples: Vec<f64>,
+}
+
+#[derive(SpatialComponent)]
+#[id(2000u32)]
+#[spatial(test_roundtrip)]
+pub struct Outer {
+    #[field_id(1u32)]
+    #[spatial_type("bool")]
+    pub flag: bool,
+    #[field_id(2u32)]
+    #[spatial_type("int32")]
+    pub small: i32,
+    #[field_id(3u32)]
+    #[spatial_type("int64")]
+    pub large: i64,
+    #[field_id(4u32)]
+    #[spatial_type("uint64")]
+    pub unsigned: u64,
+    #[field_id(5u32)]
+    #[spatial_type("string")]
+    pub name: String,
+    #[field_id(6u32)]
+    #[spatial_type("bytes")]
+    pub blob: Vec<u8>,
+    #[field_id(7u32)]
+    #[spatial_type("EntityId")]
+    pub target: i64,
+    #[field_id(8u32)]
+    #[spatial_type("enum")]
+    pub shape: Shape,
+    #[field_id(9u32)]
+    #[spatial_type("type")]
+    pub inner: Inner,
+    #[field_id(10u32)]
+    #[spatial_type("list<type>")]
+    pub inners: Vec<Inner>,
+    #[field_id(11u32)]
+    #[spatial_type("option<type>")]
+    pub maybe: Option<Inner>,
+    #[field_id(12u32)]
+    #[spatial_type("map<uint32,type>")]
+    pub by_id: HashMap<u32, Inner>,
+    #[field_id(13u32)]
+    #[spatial_type("list<enum>")]
+    pub shapes: Vec<Shape>,
+    #[field_id(14u32)]
+    #[spatial_type("option<float>")]
+    pub frequency: Option<f32>,
+    #[field_id(15u32)]
+    #[spatial_type("map<string,bytes>")]
+    pub blobs: HashMap<String, Vec<u8>>,
+}
+
+fn inner(ratio: f32) -> InnerData {
+    InnerData {
+        ratio,
+        samples: vec![1.0],
+    }
+}
+
+#[test]
+fn floats_are_compared_by_bits() {
+    assert!(Inner::data_eq(&inner(f32::NAN), &inner(f32::NAN)));
+    assert!(!Inner::data_eq(&inner(0.0), &inner(-0.0)));
+    assert!(!Inner::data_eq(&inner(1.0), &inner(2.0)));
+}
+
+#[test]
+fn generated_values_round_trip_through_buffers() {
+    let mut runner = TestRunner::deterministic();
+    for _ in 0..32 {
+        let mut data = Outer::arbitrary_data()
+            .new_tree(&mut runner)
+            .unwrap()
+            .current();
+        let mut object = MemoryObject::new();
+        Outer::data_serialize(&mut data, &mut object);
+        let mut read = MemoryObject::from_buffer(&object.serialize_to_buffer()).unwrap();
+        let result = Outer::data_deserialize(&mut read);
+        assert!(Outer::data_eq(&data, &result), "{:?} != {:?}", data, result);
+
+        let mut update = Outer::arbitrary_update()
+            .new_tree(&mut runner)
+            .unwrap()
+            .current();
+        let mut object = MemoryObject::new();
+        Outer::update_serialize(&mut update, &mut object);
+        let mut read = MemoryObject::from_buffer(&object.serialize_to_buffer()).unwrap();
+        let result = Outer::update_deserialize(&mut read);
+        assert!(
+            Outer::update_eq(&update, &result),
+            "{:?} != {:?}",
+            update,
+            result
+        );
+    }
+}
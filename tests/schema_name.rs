@@ -0,0 +1,43 @@
+mod common;
+
+use spatialos_macro::*;
+use spatialos_macro_runtime::{SchemaEnum, SchemaType};
+
+#[derive(SpatialType)]
+#[spatial(package = "improbable", name = "ComponentInterest.Query")]
+pub struct InterestQuery {}
+
+#[derive(SpatialComponent)]
+#[id(1002u32)]
+#[spatial(package = "improbable.restricted")]
+pub struct Worker {}
+
+#[derive(SpatialEnum, Clone, Debug)]
+#[spatial(name = "Status")]
+pub enum ConnectionStatus {
+    #[value(0u32)]
+    Unknown,
+}
+
+#[test]
+fn names_default_to_the_rust_name() {
+    assert_eq!(
+        <common::Coordinates as SchemaType>::SCHEMA_NAME,
+        "Coordinates"
+    );
+    assert_eq!(<common::Sample as SchemaType>::SCHEMA_NAME, "Sample");
+    assert_eq!(<common::Color as SchemaEnum>::SCHEMA_NAME, "Color");
+}
+
+#[test]
+fn names_are_qualified_by_package() {
+    assert_eq!(
+        <InterestQuery as SchemaType>::SCHEMA_NAME,
+        "improbable.ComponentInterest.Query"
+    );
+    assert_eq!(
+        <Worker as SchemaType>::SCHEMA_NAME,
+        "improbable.restricted.Worker"
+    );
+    assert_eq!(<ConnectionStatus as SchemaEnum>::SCHEMA_NAME, "Status");
+}
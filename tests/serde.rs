@@ -0,0 +1,67 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use common::*;
+
+#[test]
+fn data_serializes_fields_by_name() {
+    let mut data = sample();
+    data.tags.clear();
+    data.path.clear();
+    let json = serde_json::to_value(&data).unwrap();
+    assert_eq!(json["count"], 7);
+    assert_eq!(json["name"], "tree");
+    assert_eq!(json["blob"], "AAH/");
+    assert_eq!(json["color"], "Blue");
+    assert_eq!(json["at"]["y"], 2.0);
+    assert_eq!(json["nick"], "oak");
+    assert_eq!(json["weights"]["w"], 0.5);
+    assert_eq!(json["target"], -12);
+    assert_eq!(json["colors"], serde_json::json!(["Blue", "Red"]));
+}
+
+#[test]
+fn data_round_trip() {
+    let data = sample();
+    let json = serde_json::to_string(&data).unwrap();
+    let read: SampleData = serde_json::from_str(&json).unwrap();
+    assert_same_sample(&read, &data);
+}
+
+#[test]
+fn update_round_trip() {
+    let update = SampleUpdate {
+        count: 1,
+        name: String::new(),
+        blob: vec![1, 2, 3],
+        color: Color::Red,
+        at: None,
+        path: Some(vec![]),
+        tags: None,
+        nick: None,
+        weights: None,
+        target: 0,
+        colors: None,
+    };
+    let json = serde_json::to_value(&update).unwrap();
+    assert_eq!(json["blob"], "AQID");
+    assert!(json["at"].is_null());
+    let read: SampleUpdate = serde_json::from_value(json).unwrap();
+    assert_eq!(read.blob, vec![1, 2, 3]);
+    assert_eq!(read.path.map(|path| path.len()), Some(0));
+}
+
+#[test]
+fn unknown_variants_and_invalid_base64_are_rejected() {
+    let error = serde_json::from_str::<Color>("\"Green\"").unwrap_err();
+    assert!(error.to_string().contains("unknown variant `Green`"));
+    assert_eq!(
+        serde_json::from_str::<Color>("\"Blue\"").unwrap(),
+        Color::Blue
+    );
+
+    let mut json = serde_json::to_value(sample()).unwrap();
+    json["blob"] = "not base64!".into();
+    assert!(serde_json::from_value::<SampleData>(json).is_err());
+}
@@ -0,0 +1,49 @@
+use spatialos_macro::*;
+use spatialos_macro_runtime::schema::MemoryObject;
+use spatialos_macro_runtime::SchemaType;
+
+#[derive(SpatialType)]
+pub struct Interpolated {
+    #[field_id(1u32)]
+    #[spatial_type("double")]
+    pub value: f64,
+    #[spatial(skip)]
+    pub previous: Vec<f64>,
+    #[spatial(skip, default = 0.5)]
+    pub alpha: f64,
+}
+
+#[test]
+fn skipped_fields_take_their_default() {
+    let data = InterpolatedData::default();
+    assert!(data.previous.is_empty());
+    assert_eq!(data.alpha, 0.5);
+}
+
+#[test]
+fn skipped_fields_are_not_serialized() {
+    let mut data = InterpolatedData {
+        value: 2.0,
+        previous: vec![1.0],
+        alpha: 0.1,
+    };
+    let mut object = MemoryObject::new();
+    Interpolated::data_serialize(&mut data, &mut object);
+    assert_eq!(object.field_ids(), vec![1]);
+
+    let read = Interpolated::data_deserialize(&mut object);
+    assert_eq!(read.value, 2.0);
+    assert!(read.previous.is_empty());
+    assert_eq!(read.alpha, 0.5);
+}
+
+#[test]
+fn skipped_fields_are_kept_by_conversions() {
+    let value = Interpolated::from(InterpolatedData {
+        value: 2.0,
+        previous: vec![1.0],
+        alpha: 0.1,
+    });
+    assert_eq!(value.previous, vec![1.0]);
+    assert_eq!(InterpolatedData::from(value).alpha, 0.1);
+}
@@ -0,0 +1,219 @@
+#![cfg(feature = "snapshot")]
+
+mod common;
+
+use common::*;
+use spatialos_macro::*;
+use spatialos_macro_runtime::schema::MemoryObject;
+use spatialos_macro_runtime::snapshot::{
+    MemorySnapshotReader, MemorySnapshotWriter, SnapshotEntity, SnapshotError, MAGIC,
+};
+use spatialos_macro_runtime::template::{EntityTemplate, TemplateError};
+use spatialos_macro_runtime::SchemaObject;
+
+#[derive(SpatialComponent)]
+#[id(2000u32)]
+#[spatial(package = "test")]
+pub struct Health {
+    #[field_id(1u32)]
+    #[spatial_type("double")]
+    pub value: f64,
+}
+
+#[derive(SpatialComponent)]
+#[id(2001u32)]
+#[spatial(package = "test")]
+pub struct Paint {
+    #[field_id(1u32)]
+    #[spatial_type("enum")]
+    pub color: Color,
+}
+
+/// Serialized fields by component id.
+type Components = Vec<(u32, Vec<u8>)>;
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// A snapshot of the memory format holding the serialized fields of the components.
+fn snapshot(entities: &[(i64, Components)]) -> Vec<u8> {
+    let mut buffer = MAGIC.to_vec();
+    write_varint(&mut buffer, 1);
+    for (entity_id, components) in entities {
+        let mut object = MemoryObject::new();
+        for (id, fields) in components {
+            object.add_bytes(*id, fields);
+        }
+        let record = object.serialize_to_buffer();
+        write_varint(&mut buffer, *entity_id as u64);
+        write_varint(&mut buffer, record.len() as u64);
+        buffer.extend_from_slice(&record);
+    }
+    buffer
+}
+
+fn health_fields(value: f64, extra: Option<&str>) -> Vec<u8> {
+    let mut object = MemoryObject::new();
+    object.add_double(1, value);
+    if let Some(extra) = extra {
+        object.add_string(99, extra);
+    }
+    object.serialize_to_buffer()
+}
+
+/// Fields of `Paint` whose color isn't a value of `Color`.
+fn invalid_paint() -> Vec<u8> {
+    let mut object = MemoryObject::new();
+    object.add_uint32(1, 1);
+    object.serialize_to_buffer()
+}
+
+fn read_all(bytes: &[u8]) -> Vec<SnapshotEntity> {
+    MemorySnapshotReader::new(bytes)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap()
+}
+
+fn write_all(entities: &mut [SnapshotEntity]) -> Vec<u8> {
+    let mut writer = MemorySnapshotWriter::new(Vec::new()).unwrap();
+    for entity in entities {
+        writer.write_entity(entity).unwrap();
+    }
+    writer.into_inner()
+}
+
+#[test]
+fn entities_roundtrip() {
+    let template = EntityTemplate::new()
+        .with(sample())
+        .unwrap()
+        .with(HealthData { value: 0.5 })
+        .unwrap();
+    let bytes = write_all(&mut [
+        SnapshotEntity::new(1, template),
+        SnapshotEntity::new(2, EntityTemplate::new()),
+    ]);
+
+    let entities = read_all(&bytes);
+    assert_eq!(entities.len(), 2);
+    assert_eq!(entities[0].entity_id, 1);
+    assert_eq!(entities[0].component_ids(), [1000, 2000]);
+    assert_same_sample(entities[0].get::<Sample>().unwrap(), &sample());
+    assert_eq!(entities[0].get::<Health>().unwrap().value, 0.5);
+    assert_eq!(
+        entities[0].raw_component(2000),
+        Some(&health_fields(0.5, None)[..])
+    );
+    assert_eq!(entities[1].entity_id, 2);
+    assert!(entities[1].component_ids().is_empty());
+}
+
+#[test]
+fn components_are_written_back_as_read() {
+    let bytes = snapshot(&[(
+        7,
+        vec![
+            (2000, health_fields(3.0, Some("kept"))),
+            (9000, vec![8, 1, 16, 2]),
+        ],
+    )]);
+    let mut entities = read_all(&bytes);
+    assert_eq!(entities[0].get::<Health>().unwrap().value, 3.0);
+    assert_eq!(
+        entities[0].components().component_ids().collect::<Vec<_>>(),
+        [2000]
+    );
+    assert_eq!(entities[0].raw_component(9000), Some(&[8, 1, 16, 2][..]));
+
+    assert_eq!(write_all(&mut entities), bytes);
+}
+
+#[test]
+fn components_which_fail_to_decode_are_kept() {
+    let bytes = snapshot(&[(3, vec![(2001, invalid_paint())])]);
+    let mut entities = read_all(&bytes);
+    assert!(entities[0].get::<Paint>().is_none());
+    assert!(entities[0].get_mut::<Paint>().is_none());
+    assert_eq!(entities[0].component_ids(), [2001]);
+    assert_eq!(entities[0].raw_component(2001), Some(&invalid_paint()[..]));
+    assert_eq!(
+        entities[0].add(PaintData { color: Color::Red }),
+        Err(TemplateError::DuplicateComponent {
+            id: 2001,
+            schema_name: "test.Paint",
+        })
+    );
+
+    assert_eq!(write_all(&mut entities), bytes);
+}
+
+#[test]
+fn changed_components_are_serialized_again() {
+    let bytes = snapshot(&[(7, vec![(2000, health_fields(3.0, Some("dropped")))])]);
+    let mut entities = read_all(&bytes);
+    entities[0].get_mut::<Health>().unwrap().value = 4.0;
+    assert_eq!(entities[0].raw_component(2000), None);
+
+    let entities = read_all(&write_all(&mut entities));
+    assert_eq!(entities[0].get::<Health>().unwrap().value, 4.0);
+    assert_eq!(
+        entities[0].raw_component(2000),
+        Some(&health_fields(4.0, None)[..])
+    );
+}
+
+#[test]
+fn removed_components_are_not_written() {
+    let bytes = snapshot(&[(
+        7,
+        vec![(2000, health_fields(3.0, None)), (9000, vec![8, 1])],
+    )]);
+    let mut entities = read_all(&bytes);
+    assert!(entities[0].remove::<Health>());
+    assert!(!entities[0].remove::<Health>());
+
+    let entities = read_all(&write_all(&mut entities));
+    assert_eq!(entities[0].component_ids(), [9000]);
+}
+
+#[test]
+fn required_components_are_checked() {
+    let mut entity = SnapshotEntity::new(3, EntityTemplate::new().require::<Paint>());
+    let mut writer = MemorySnapshotWriter::new(Vec::new()).unwrap();
+    assert!(matches!(
+        writer.write_entity(&mut entity),
+        Err(SnapshotError::Template(TemplateError::MissingComponent {
+            id: 2001,
+            ..
+        }))
+    ));
+    entity.add(PaintData { color: Color::Blue }).unwrap();
+    writer.write_entity(&mut entity).unwrap();
+}
+
+#[test]
+fn duplicate_components_are_errors() {
+    let bytes = snapshot(&[(1, vec![(9000, vec![8, 1]), (9000, vec![8, 2])])]);
+    let error = MemorySnapshotReader::new(&bytes[..])
+        .unwrap()
+        .read_entity()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        SnapshotError::Template(TemplateError::DuplicateComponent { id: 9000, .. })
+    ));
+}
+
+#[test]
+fn other_files_are_rejected() {
+    assert!(matches!(
+        MemorySnapshotReader::new(&b"NOTASNAPSHOT"[..]),
+        Err(SnapshotError::InvalidMagic)
+    ));
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "snapshot")]
+
+use spatialos_macro::*;
+use spatialos_macro_runtime::snapshot::{
+    MemorySnapshotReader, MemorySnapshotWriter, SnapshotError,
+};
+
+#[derive(SpatialComponent)]
+#[id(3000u32)]
+#[spatial(package = "test")]
+pub struct First {}
+
+#[derive(SpatialComponent)]
+#[id(3000u32)]
+#[spatial(package = "test")]
+pub struct Second {}
+
+#[test]
+fn shared_ids_fail_the_reader() {
+    let bytes = MemorySnapshotWriter::new(Vec::new()).unwrap().into_inner();
+    match MemorySnapshotReader::new(&bytes[..]) {
+        Err(SnapshotError::DuplicateComponentId(duplicate)) => assert_eq!(duplicate.id, 3000),
+        _ => panic!("expected the shared id to be an error"),
+    }
+}
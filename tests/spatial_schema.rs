@@ -0,0 +1,85 @@
+use spatialos_macro::*;
+use spatialos_macro_runtime::schema::MemoryObject;
+use spatialos_macro_runtime::{SchemaComponent, SchemaEnum, SchemaObject, SchemaType};
+
+spatial_schema! {
+    package game;
+
+    /// A point in space.
+    type Vec3 {
+        double x = 1;
+        double y = 2;
+        double z = 3;
+    }
+
+    enum Team {
+        RED = 1;
+        DEEP_BLUE = 2;
+    }
+
+    type HealthState {
+        float hp = 1;
+        Team team = 2;
+        option<Vec3> respawn = 3;
+    }
+
+    component Health {
+        id = 1000;
+        data HealthState;
+    }
+
+    component Movement {
+        id = 1001;
+        Vec3 velocity = 1;
+        [game.Replicated]
+        list<Team> allies = 2;
+        command Vec3 teleport(Vec3);
+        event Vec3 moved;
+    }
+}
+
+#[test]
+fn inline_items_are_generated() {
+    assert_eq!(<Vec3 as SchemaType>::SCHEMA_NAME, "game.Vec3");
+    assert_eq!(<Team as SchemaEnum>::SCHEMA_NAME, "game.Team");
+    assert_eq!(Team::from_value(1), Team::Red);
+    assert_eq!(Team::VALUES, &[1, 2]);
+    assert_eq!(<Health as SchemaComponent>::ID, 1000);
+    assert_eq!(<Movement as SchemaComponent>::ID, 1001);
+}
+
+#[test]
+fn components_use_their_data_type() {
+    let mut data = HealthData {
+        hp: 10.0,
+        team: Team::DeepBlue,
+        respawn: Some(Vec3Data {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        }),
+    };
+    let mut object = MemoryObject::new();
+    Health::data_serialize(&mut data, &mut object);
+    assert_eq!(object.get_float(1), 10.0);
+    assert_eq!(object.get_enum::<u32>(2), 2);
+
+    let read = HealthState::data_deserialize(&mut object);
+    assert_eq!(read.team, Team::DeepBlue);
+    assert_eq!(read.respawn.unwrap().x, 1.0);
+}
+
+#[test]
+fn commands_and_events_are_ignored() {
+    let mut data = MovementData {
+        velocity: Vec3Data {
+            x: 0.0,
+            y: 2.0,
+            z: 0.0,
+        },
+        allies: vec![Team::Red],
+    };
+    let mut object = MemoryObject::new();
+    Movement::data_serialize(&mut data, &mut object);
+    assert_eq!(object.field_ids(), vec![1, 2]);
+}
@@ -0,0 +1,80 @@
+use spatialos_macro::*;
+use spatialos_macro_runtime::schema::{MemoryObject, SchemaErrorKind};
+use spatialos_macro_runtime::{FieldCountError, SchemaObject, SchemaType};
+
+#[derive(SpatialType)]
+#[spatial(strict)]
+pub struct Point {
+    #[field_id(1u32)]
+    #[spatial_type("int32")]
+    pub x: i32,
+}
+
+#[derive(SpatialType)]
+#[spatial(package = "test", strict)]
+pub struct Thing {
+    #[field_id(1u32)]
+    #[spatial_type("uint32")]
+    pub count: u32,
+    #[field_id(2u32)]
+    #[spatial_type("option<string>")]
+    pub nick: Option<String>,
+    #[field_id(3u32)]
+    #[spatial_type("list<type>")]
+    pub points: Vec<Point>,
+}
+
+fn thing() -> MemoryObject {
+    let mut object = MemoryObject::new();
+    object.add_uint32(1, 5);
+    object.add_object(3).add_int32(1, 2);
+    object
+}
+
+#[test]
+fn valid_objects_are_read() {
+    let data = Thing::strict_data_deserialize(&mut thing()).unwrap();
+    assert_eq!(data.count, 5);
+    assert_eq!(data.nick, None);
+    assert_eq!(data.points[0].x, 2);
+}
+
+#[test]
+fn field_counts_are_checked() {
+    let mut object = thing();
+    object.add_uint32(1, 6);
+    let error = Thing::strict_data_deserialize(&mut object).unwrap_err();
+    assert_eq!(
+        error.kind,
+        SchemaErrorKind::FieldCount(FieldCountError {
+            type_name: "test.Thing",
+            field: "count",
+            field_id: 1,
+            count: 2,
+        })
+    );
+
+    let mut object = MemoryObject::new();
+    object.add_string(2, "a");
+    object.add_string(2, "b");
+    let error = Thing::strict_data_deserialize(&mut object).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "test.Thing: missing required field count (id 1)"
+    );
+}
+
+#[test]
+fn unknown_field_ids_are_rejected() {
+    let mut object = thing();
+    object.add_uint32(999, 5);
+    let error = Thing::strict_data_deserialize(&mut object).unwrap_err();
+    assert_eq!(error.kind, SchemaErrorKind::UnknownField(999));
+    assert_eq!(error.to_string(), "test.Thing: unknown field id 999");
+    assert!(Thing::try_deserialize_data(&mut object).is_err());
+
+    let mut object = thing();
+    object.index_object(3, 0).add_bool(2, true);
+    let error = Thing::strict_data_deserialize(&mut object).unwrap_err();
+    assert_eq!(error.to_string(), "Point: unknown field id 2");
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::*;
+use spatialos_macro::*;
+use spatialos_macro_runtime::template::{EntityTemplate, TemplateError};
+use spatialos_macro_runtime::SchemaType;
+
+#[derive(SpatialComponent)]
+#[spatial(package = "improbable")]
+#[id(50u32)]
+pub struct EntityAcl {
+    #[field_id(1u32)]
+    #[spatial_type("list<string>")]
+    pub read_attributes: Vec<String>,
+}
+
+#[derive(SpatialComponent)]
+#[spatial(package = "improbable")]
+#[id(54u32)]
+pub struct Position {
+    #[field_id(1u32)]
+    #[spatial_type("type")]
+    pub coords: Coordinates,
+}
+
+fn acl() -> EntityAclData {
+    EntityAclData {
+        read_attributes: vec!["client".to_owned()],
+    }
+}
+
+fn position() -> PositionData {
+    PositionData {
+        coords: coordinates(1.0, 2.0, 3.0),
+    }
+}
+
+#[test]
+fn standard_templates_require_the_acl_and_position() {
+    let mut template = EntityTemplate::standard::<EntityAcl, Position>();
+    template.add(position()).unwrap();
+    assert_eq!(
+        template.check(),
+        Err(TemplateError::MissingComponent {
+            id: 50,
+            schema_name: "improbable.EntityAcl",
+        })
+    );
+    template.add(acl()).unwrap();
+    assert_eq!(template.check(), Ok(()));
+}
+
+#[test]
+fn new_templates_require_what_is_asked() {
+    assert_eq!(EntityTemplate::new().check(), Ok(()));
+    let template = EntityTemplate::new().require::<Sample>();
+    assert_eq!(
+        template.check(),
+        Err(TemplateError::MissingComponent {
+            id: 1000,
+            schema_name: "Sample",
+        })
+    );
+}
+
+#[test]
+fn components_are_unique() {
+    let mut template = EntityTemplate::new().with(position()).unwrap();
+    assert_eq!(
+        template.add(position()).unwrap_err(),
+        TemplateError::DuplicateComponent {
+            id: 54,
+            schema_name: "improbable.Position",
+        }
+    );
+    assert!(template.remove::<Position>());
+    assert!(!template.contains(54));
+    template.add(position()).unwrap();
+}
+
+#[test]
+fn components_are_read_back_and_serialized_in_id_order() {
+    let mut template = EntityTemplate::standard::<EntityAcl, Position>()
+        .with(sample())
+        .unwrap()
+        .with(position())
+        .unwrap()
+        .with(acl())
+        .unwrap();
+    assert_eq!(template.component_ids().collect::<Vec<_>>(), [50, 54, 1000]);
+    assert_same_sample(template.get::<Sample>().unwrap(), &sample());
+    template.get_mut::<Position>().unwrap().coords.x = 9.0;
+
+    let mut objects = template.to_memory_objects().unwrap();
+    let ids = objects.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    assert_eq!(ids, [50, 54, 1000]);
+    assert_eq!(
+        EntityAcl::data_deserialize(&mut objects[0].1).read_attributes,
+        ["client"]
+    );
+    assert_same_coordinates(
+        &Position::data_deserialize(&mut objects[1].1).coords,
+        &coordinates(9.0, 2.0, 3.0),
+    );
+    assert_same_sample(&Sample::data_deserialize(&mut objects[2].1), &sample());
+}
+
+#[test]
+fn missing_components_fail_the_serialization() {
+    let mut template = EntityTemplate::standard::<EntityAcl, Position>()
+        .with(acl())
+        .unwrap();
+    assert!(template.to_memory_objects().is_err());
+}
@@ -0,0 +1,58 @@
+use spatialos_macro::*;
+
+#[derive(SpatialComponent)]
+#[id(1003u32)]
+#[spatial(tracked)]
+pub struct Health {
+    #[field_id(1u32)]
+    #[spatial_type("float")]
+    pub current: f32,
+    #[field_id(2u32)]
+    #[spatial_type("float")]
+    pub max: f32,
+    #[field_id(3u32)]
+    #[spatial_type("list<string>")]
+    pub effects: Vec<String>,
+    #[spatial(skip)]
+    pub shown: f32,
+}
+
+fn health() -> HealthData {
+    HealthData {
+        current: 5.0,
+        max: 10.0,
+        effects: vec!["burn".to_owned()],
+        shown: 0.0,
+    }
+}
+
+#[test]
+fn setters_record_changed_fields() {
+    let mut tracked = TrackedHealthData::new(health());
+    assert!(!tracked.is_dirty());
+
+    tracked.set_current(4.0);
+    tracked.effects_mut().clear();
+    tracked.set_shown(4.5);
+    assert_eq!(*tracked.current(), 4.0);
+    assert_eq!(*tracked.shown(), 4.5);
+    assert_eq!(tracked.dirty_fields().collect::<Vec<_>>(), vec![1, 3]);
+
+    let update = tracked.take_update();
+    assert!(!tracked.is_dirty());
+    assert_eq!(update.current, 4.0);
+    assert_eq!(update.effects, Some(Vec::new()));
+    assert_eq!(update.max, 10.0);
+
+    let update = tracked.take_update();
+    assert_eq!(update.effects, None);
+    assert_eq!(tracked.into_data().current, 4.0);
+}
+
+#[test]
+fn data_converts_into_a_full_update() {
+    let update = HealthUpdate::from(health());
+    assert_eq!(update.current, 5.0);
+    assert_eq!(update.max, 10.0);
+    assert_eq!(update.effects, Some(vec!["burn".to_owned()]));
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::*;
+use spatialos_macro::*;
+use spatialos_macro_runtime::schema::MemoryObject;
+use spatialos_macro_runtime::{SchemaObject, SchemaType};
+use std::borrow::Cow;
+
+#[derive(SpatialType)]
+pub struct Label {
+    #[field_id(1u32)]
+    #[spatial_type("string")]
+    #[spatial(default = "none".to_owned())]
+    pub text: String,
+    #[field_id(2u32)]
+    #[spatial_type("bytes")]
+    pub raw: Vec<u8>,
+    #[field_id(3u32)]
+    #[spatial_type("map<string,bytes>")]
+    pub names: std::collections::HashMap<String, Vec<u8>>,
+}
+
+#[test]
+fn strings_and_bytes_read_through_views() {
+    let mut data = sample();
+    let mut object = MemoryObject::new();
+    Sample::data_serialize(&mut data, &mut object);
+
+    let mut view = SampleView::new(&mut object);
+    let name: Cow<'_, str> = view.name();
+    assert_eq!(name, "tree");
+    let blob: Cow<'_, [u8]> = view.blob();
+    assert_eq!(&*blob, &[0, 1, 255]);
+    assert_eq!(view.tags().collect::<Vec<_>>(), ["a", "b"]);
+    assert_eq!(view.nick().as_deref(), Some("oak"));
+    let weights: Vec<(String, f64)> = view.weights().collect();
+    assert_eq!(weights, [("w".to_owned(), 0.5)]);
+}
+
+#[test]
+fn missing_strings_and_bytes_use_the_defaults() {
+    let mut object = MemoryObject::new();
+    let mut view = LabelView::new(&mut object);
+    assert_eq!(view.text(), "none");
+    assert!(view.raw().is_empty());
+    assert_eq!(view.names().len(), 0);
+}
+
+#[test]
+fn map_entries_copy_strings_and_bytes() {
+    let mut data = LabelData {
+        text: "label".to_owned(),
+        raw: vec![9],
+        names: vec![("one".to_owned(), vec![1, 2])].into_iter().collect(),
+    };
+    let mut object = MemoryObject::new();
+    Label::data_serialize(&mut data, &mut object);
+
+    let mut view = LabelView::new(&mut object);
+    let names: Vec<(String, Vec<u8>)> = view.names().collect();
+    assert_eq!(names, [("one".to_owned(), vec![1, 2])]);
+    assert_eq!(view.text(), "label");
+    assert_eq!(&*view.raw(), &[9]);
+}
+
+#[test]
+fn invalid_strings_are_read_lossily() {
+    let mut object = MemoryObject::new();
+    object.add_bytes(1, &[b'a', 0xff]);
+    let mut entry = object.add_object(3);
+    entry.add_bytes(1, &[0xff]);
+    entry.add_bytes(2, &[1]);
+
+    let mut view = LabelView::new(&mut object);
+    assert_eq!(view.text(), "a\u{FFFD}");
+    let names: Vec<(String, Vec<u8>)> = view.names().collect();
+    assert_eq!(names, [("\u{FFFD}".to_owned(), vec![1])]);
+}
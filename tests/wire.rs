@@ -0,0 +1,135 @@
+use spatialos_macro_runtime::schema::{MemoryObject, WireError};
+use spatialos_macro_runtime::SchemaObject;
+
+#[test]
+fn values_are_encoded_like_protobuf_fields() {
+    let mut object = MemoryObject::new();
+    object.add_uint32(1, 150u32);
+    object.add_sint32(2, -2i32);
+    object.add_int32(3, -1i32);
+    object.add_double(4, 1.0f64);
+    object.add_float(5, 1.0f32);
+    object.add_string(6, "hi");
+    object.add_bool(16, true);
+
+    let mut expected = vec![8, 150, 1, 16, 3, 24];
+    expected.extend_from_slice(&[255, 255, 255, 255, 255, 255, 255, 255, 255, 1]);
+    expected.push(33);
+    expected.extend_from_slice(&1.0f64.to_le_bytes());
+    expected.push(45);
+    expected.extend_from_slice(&1.0f32.to_le_bytes());
+    expected.extend_from_slice(&[50, 2, b'h', b'i', 128, 1, 1]);
+    assert_eq!(object.serialize_to_buffer(), expected);
+}
+
+#[test]
+fn nested_objects_are_length_delimited() {
+    let mut object = MemoryObject::new();
+    object.add_object(1).add_uint32(1, 1u32);
+
+    assert_eq!(object.serialize_to_buffer(), vec![10, 2, 8, 1]);
+}
+
+#[test]
+fn every_primitive_round_trips() {
+    let mut object = MemoryObject::new();
+    object.add_bool(1, true);
+    object.add_float(2, -0.5f32);
+    object.add_double(3, 1e300f64);
+    object.add_int32(4, i32::MIN);
+    object.add_int64(5, i64::MIN);
+    object.add_uint32(6, u32::MAX);
+    object.add_uint64(7, u64::MAX);
+    object.add_sint32(8, i32::MIN);
+    object.add_sint64(9, i64::MIN);
+    object.add_fixed32(10, u32::MAX);
+    object.add_fixed64(11, u64::MAX);
+    object.add_sfixed32(12, -3i32);
+    object.add_sfixed64(13, -4i64);
+    object.add_entity_id(14, -5i64);
+    object.add_bytes(15, &[0, 255]);
+    object.add_uint32_list(16, &[1, 2, 3]);
+    object.add_object(17).add_string(1, "inner");
+
+    let mut read = MemoryObject::from_buffer(&object.serialize_to_buffer()).unwrap();
+    assert_eq!(read.field_ids(), (1..=17).collect::<Vec<_>>());
+    assert!(read.get_bool(1));
+    assert_eq!(read.get_float(2), -0.5);
+    assert_eq!(read.get_double(3), 1e300);
+    assert_eq!(read.get_int32(4), i32::MIN);
+    assert_eq!(read.get_int64(5), i64::MIN);
+    assert_eq!(read.get_uint32(6), u32::MAX);
+    assert_eq!(read.get_uint64(7), u64::MAX);
+    assert_eq!(read.get_sint32(8), i32::MIN);
+    assert_eq!(read.get_sint64(9), i64::MIN);
+    assert_eq!(read.get_fixed32(10), u32::MAX);
+    assert_eq!(read.get_fixed64(11), u64::MAX);
+    assert_eq!(read.get_sfixed32(12), -3);
+    assert_eq!(read.get_sfixed64(13), -4);
+    assert_eq!(read.get_entity_id(14), -5);
+    assert_eq!(read.get_bytes(15), vec![0, 255]);
+    assert_eq!(read.get_uint32_list(16), vec![1, 2, 3]);
+    assert_eq!(read.get_object(17).get_string(1), "inner");
+}
+
+#[test]
+fn merging_appends_values() {
+    let mut first = MemoryObject::new();
+    first.add_uint32(1, 1u32);
+    let mut second = MemoryObject::new();
+    second.add_uint32(1, 2u32);
+    second.add_string(2, "two");
+
+    let mut object = MemoryObject::from_buffer(&first.serialize_to_buffer()).unwrap();
+    object
+        .merge_from_buffer(&second.serialize_to_buffer())
+        .unwrap();
+    assert_eq!(object.get_uint32_list(1), vec![1, 2]);
+    assert_eq!(object.get_string(2), "two");
+}
+
+#[test]
+fn empty_buffer_is_an_empty_object() {
+    let object = MemoryObject::from_buffer(&[]).unwrap();
+    assert!(object.field_ids().is_empty());
+}
+
+#[test]
+fn truncated_buffers_are_rejected() {
+    assert_eq!(
+        MemoryObject::from_buffer(&[8]).unwrap_err(),
+        WireError::Truncated
+    );
+    assert_eq!(
+        MemoryObject::from_buffer(&[8, 150]).unwrap_err(),
+        WireError::Truncated
+    );
+    assert_eq!(
+        MemoryObject::from_buffer(&[13, 0, 0]).unwrap_err(),
+        WireError::Truncated
+    );
+    assert_eq!(
+        MemoryObject::from_buffer(&[10, 3, 1, 2]).unwrap_err(),
+        WireError::Truncated
+    );
+}
+
+#[test]
+fn invalid_tags_are_rejected() {
+    assert_eq!(
+        MemoryObject::from_buffer(&[0, 1]).unwrap_err(),
+        WireError::InvalidFieldId(0)
+    );
+    assert_eq!(
+        MemoryObject::from_buffer(&[128, 128, 128, 128, 128, 1, 0]).unwrap_err(),
+        WireError::InvalidFieldId(1 << 32)
+    );
+    assert_eq!(
+        MemoryObject::from_buffer(&[11]).unwrap_err(),
+        WireError::InvalidWireType(3)
+    );
+    assert_eq!(
+        WireError::InvalidWireType(3).to_string(),
+        "invalid wire type 3"
+    );
+}